@@ -1,14 +1,25 @@
 use std::{
-    collections::{HashMap, HashSet},
-    fs::File,
-    io::Read,
+    collections::HashSet,
     path::{Path, PathBuf},
 };
 
 use serde::{Deserialize, Serialize};
-use time::{Date, Time, macros::format_description};
+use time::{Date, Weekday, macros::format_description};
+
+#[path = "src/model.rs"]
+mod model;
+#[path = "src/parse.rs"]
+mod parse;
+
+use model::{CALENDAR_LAYERS, Calendar, Meta, ScheduleStore, SpecialDay};
 
 fn main() {
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
+    let data_out = out_dir.join("data.postcard");
+    if let Some(prebuilt) = option_env!("BELL_PREBUILT_DATA") {
+        copy_prebuilt_data(Path::new(prebuilt), &data_out);
+        return;
+    }
     let selected_schedule = option_env!("SELECTED_SCHEDULE").unwrap_or("lahs");
     let schedule_dir = option_env!("SCHEDULE_DIR").unwrap_or("schedules");
     let schedule = PathBuf::from(format!("{}/{}", schedule_dir, selected_schedule));
@@ -19,267 +30,285 @@ fn main() {
         );
     }
     let meta = read_meta(&schedule.join("meta.json"));
-    let calendar = read_calendar(&schedule.join("calendar.bell"));
+    let calendars = read_calendars(&schedule);
     let schedules = read_schedules(&schedule.join("schedules.bell"));
-    verify_schedules(&schedules, &calendar);
+    verify_schedules(&schedules, &calendars);
+    parse::check_inserts(&meta, &calendars, &schedules).unwrap_or_else(|err| panic!("{}", err));
+    verify_period_names(&schedules, &meta);
+    for calendar in &calendars {
+        verify_special_days(calendar);
+    }
+    verify_day_window(&meta, &schedules);
+    if option_env!("REPORT_CALENDAR_GAPS").is_some_and(|val| val != "0") {
+        report_default_week_runs(&meta, &calendars, &schedules);
+    }
 
-    let out_dir = PathBuf::from(std::env::var("OUT_DIR").unwrap());
-    let data_out = out_dir.join("data.postcard");
     let data = AppData {
         meta,
-        calendar,
+        calendars,
         schedules,
     };
-    let data_bytes = postcard::to_stdvec(&data).expect("Failed to serialize data");
+    let mut data_bytes = model::data_header();
+    data_bytes.extend(postcard::to_stdvec(&data).expect("Failed to serialize data"));
     std::fs::write(data_out, data_bytes).expect("Failed to write data.postcard");
 }
 
-fn verify_schedules(schedules: &ScheduleStore, calendar: &Calendar) {
-    let mut calendar_schedules = HashSet::new();
-    let week = &calendar.default;
-    if let Some(name) = &week.sun {
-        calendar_schedules.insert(name.clone());
-    }
-    if let Some(name) = &week.mon {
-        calendar_schedules.insert(name.clone());
-    }
-    if let Some(name) = &week.tue {
-        calendar_schedules.insert(name.clone());
-    }
-    if let Some(name) = &week.wed {
-        calendar_schedules.insert(name.clone());
-    }
-    if let Some(name) = &week.thu {
-        calendar_schedules.insert(name.clone());
-    }
-    if let Some(name) = &week.fri {
-        calendar_schedules.insert(name.clone());
+// A blob from an earlier build (OUT_DIR/data.postcard) skips parsing the schedule
+// directory, but it still has to decode with the current data layout.
+fn copy_prebuilt_data(prebuilt: &Path, data_out: &Path) {
+    let bytes = std::fs::read(prebuilt).unwrap_or_else(|err| {
+        panic!(
+            "Failed to read BELL_PREBUILT_DATA {}: {}",
+            prebuilt.display(),
+            err
+        )
+    });
+    let payload = model::data_payload(&bytes).unwrap_or_else(|err| {
+        panic!(
+            "BELL_PREBUILT_DATA {} is not valid schedule data: {}",
+            prebuilt.display(),
+            err
+        )
+    });
+    if let Err(err) = postcard::from_bytes::<AppData>(payload) {
+        panic!(
+            "BELL_PREBUILT_DATA {} is not valid schedule data: {}",
+            prebuilt.display(),
+            err
+        );
     }
-    if let Some(name) = &week.sat {
-        calendar_schedules.insert(name.clone());
+    std::fs::write(data_out, bytes).expect("Failed to write data.postcard");
+}
+
+fn verify_schedules(schedules: &ScheduleStore, calendars: &[Calendar]) {
+    let mut calendar_schedules = HashSet::new();
+    let weekdays = [
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+        Weekday::Sunday,
+    ];
+    for calendar in calendars {
+        for weekday in weekdays {
+            if let Some(name) = calendar.default.schedule_for(weekday) {
+                calendar_schedules.insert(name.to_string());
+            }
+        }
+        for special in &calendar.special {
+            calendar_schedules.extend(special.schedule.clone());
+        }
     }
-    for special in &calendar.special {
-        calendar_schedules.insert(special.schedule.clone());
+    for schedule in schedules.schedules.values() {
+        calendar_schedules.extend(schedule.based_on.clone());
     }
     for name in schedules.schedules.keys() {
         if !calendar_schedules.contains(name) {
             panic!("Schedule '{}' is not referenced in calendar", name);
         }
     }
-}
-
-fn read_meta(meta_path: &Path) -> Meta {
-    let meta_data = std::fs::read_to_string(meta_path).expect("Failed to read meta.json");
-    let meta: Meta = serde_json::from_str(&meta_data).expect("Failed to parse meta.json");
-    meta
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Meta {
-    pub name: String,
-    pub periods: Vec<String>,
-}
-
-fn read_calendar(calendar: &Path) -> Calendar {
-    let date_format = format_description!("[month]/[day]/[year]");
-    let mut file = File::open(calendar).unwrap();
-    let mut buf = String::with_capacity(file.metadata().unwrap().len() as usize);
-    file.read_to_string(&mut buf).unwrap();
-    let mut iter = buf.lines().map(|el| el.trim());
-    if iter.next().unwrap() != "* Default Week" {
-        panic!("Invalid start of calendar file");
-    }
-    let mut default_week = Week::default();
-    for next in iter.by_ref() {
-        if next.is_empty() {
+    for special in calendars.iter().flat_map(|calendar| &calendar.special) {
+        let Some(name) = &special.schedule else {
             continue;
-        }
-        if next == "* Special Days" {
-            break;
-        }
-        let mut parts = next.split_whitespace();
-        let day = parts.next().unwrap_or("").trim();
-        let schedule = parts.next().unwrap_or("").trim();
-        if schedule.is_empty() {
-            panic!("Missing schedule for default week");
-        }
-        match day {
-            "Sun" => default_week.sun = Some(schedule.to_string()),
-            "Mon" => default_week.mon = Some(schedule.to_string()),
-            "Tue" => default_week.tue = Some(schedule.to_string()),
-            "Wed" => default_week.wed = Some(schedule.to_string()),
-            "Thu" => default_week.thu = Some(schedule.to_string()),
-            "Fri" => default_week.fri = Some(schedule.to_string()),
-            "Sat" => default_week.sat = Some(schedule.to_string()),
-            _ => panic!("Invalid day in default week"),
-        }
-    }
-    let mut special_days = Vec::new();
-    for next in iter {
-        if next.is_empty() {
+        };
+        let Some(schedule) = schedules.schedules.get(name) else {
             continue;
-        }
-        let (before_comment, comment) = match next.split_once('#') {
-            Some((left, right)) => (left.trim(), Some(right.trim().to_string())),
-            None => (next.trim(), None),
         };
-        let mut parts = before_comment.split_whitespace();
-        let date_str = parts.next().unwrap_or("").trim();
-        let schedule = parts.next().unwrap_or("").trim();
-        if date_str.is_empty() || schedule.is_empty() {
-            panic!("Invalid special day entry");
+        for period_override in &special.overrides {
+            if !schedule
+                .periods
+                .iter()
+                .any(|period| period.msg == period_override.msg)
+            {
+                panic!(
+                    "Override for unknown period '{}' in schedule '{}'",
+                    period_override.msg, name
+                );
+            }
         }
-        let (on, until) = if date_str.contains('-') {
-            let mut dates = date_str.splitn(2, '-');
-            let on = Date::parse(dates.next().unwrap(), date_format).unwrap();
-            let until = Date::parse(dates.next().unwrap(), date_format).unwrap();
-            (on, Some(until))
-        } else {
-            let on = Date::parse(date_str, date_format).unwrap();
-            (on, None)
-        };
-        special_days.push(SpecialDay {
-            on,
-            until,
-            schedule: schedule.to_string(),
-            comment,
-        });
-    }
-    Calendar {
-        default: default_week,
-        special: special_days,
     }
 }
 
-fn read_schedules(schedules_path: &Path) -> ScheduleStore {
-    let mut file = File::open(schedules_path).unwrap();
-    let mut buf = String::with_capacity(file.metadata().unwrap().len() as usize);
-    file.read_to_string(&mut buf).unwrap();
-    let iter = buf.lines().map(|el| el.trim());
-    let mut schedules: HashMap<String, Schedule> = HashMap::new();
-    let mut current_name: Option<String> = None;
-    let mut current_comment: Option<String> = None;
-    let mut current_periods: Vec<Period> = Vec::new();
-
-    for next in iter {
-        if next.is_empty() {
-            continue;
-        }
-        if let Some(header) = next.strip_prefix('*') {
-            if let Some(name) = current_name.take() {
-                let schedule = Schedule {
-                    comment: current_comment.take(),
-                    periods: std::mem::take(&mut current_periods),
-                };
-                if schedules.insert(name, schedule).is_some() {
-                    panic!("Duplicate schedule name in schedules.bell");
+fn verify_period_names(schedules: &ScheduleStore, meta: &Meta) {
+    let strict = option_env!("STRICT_PERIOD_NAMES").is_some_and(|val| val != "0");
+    let known: HashSet<&str> = meta.periods.iter().map(|name| name.as_str()).collect();
+    let mut reported = HashSet::new();
+    let mut names: Vec<&String> = schedules.schedules.keys().collect();
+    names.sort();
+    for name in names {
+        // Hidden periods are markers, never shown, so they need no meta.json entry.
+        let periods = &schedules.schedules[name].periods;
+        for period in periods.iter().filter(|period| !period.hidden) {
+            for referenced in period_references(&period.msg) {
+                if known.contains(referenced) || !reported.insert(referenced) {
+                    continue;
                 }
+                let message = format!(
+                    "Period '{}' in schedule '{}' is not listed in meta.json periods",
+                    referenced, name
+                );
+                if strict {
+                    panic!("{}", message);
+                }
+                println!("cargo:warning={}", message);
             }
-            let header = header.trim();
-            let (before_comment, comment) = match header.split_once('#') {
-                Some((left, right)) => (left.trim(), Some(right.trim().to_string())),
-                None => (header, None),
-            };
-            let mut parts = before_comment.split_whitespace();
-            let name = parts.next().unwrap_or("").trim();
-            if name.is_empty() {
-                panic!("Missing schedule name in schedules.bell");
-            }
-            current_name = Some(name.to_string());
-            current_comment = comment.filter(|val| !val.is_empty());
-            continue;
-        }
-        if current_name.is_none() {
-            panic!("Schedule period found before any schedule header");
-        }
-        let (start, msg) = split_start_message(next);
-        let start = parse_start_time(start);
-        current_periods.push(Period {
-            start,
-            msg: msg.to_string(),
-        });
-    }
-    if let Some(name) = current_name.take() {
-        let schedule = Schedule {
-            comment: current_comment.take(),
-            periods: std::mem::take(&mut current_periods),
-        };
-        if schedules.insert(name, schedule).is_some() {
-            panic!("Duplicate schedule name in schedules.bell");
         }
     }
-
-    ScheduleStore { schedules }
 }
 
-fn split_start_message(line: &str) -> (&str, &str) {
-    if let Some((idx, _)) = line.char_indices().find(|(_, ch)| ch.is_whitespace()) {
-        let (start, rest) = line.split_at(idx);
-        let start = start.trim();
-        let msg = rest.trim();
-        if start.is_empty() || msg.is_empty() {
-            panic!("Invalid period entry in schedules.bell");
+fn period_references(msg: &str) -> Vec<&str> {
+    let mut refs = Vec::new();
+    let mut rest = msg;
+    while let Some((_, after)) = rest.split_once('{') {
+        match after.split_once('}') {
+            Some((name, remaining)) => {
+                refs.push(name.trim());
+                rest = remaining;
+            }
+            None => break,
         }
-        (start, msg)
-    } else {
-        panic!("Invalid period entry in schedules.bell");
     }
+    if refs.is_empty() {
+        refs.push(msg.trim());
+    }
+    refs
 }
 
-fn parse_start_time(raw: &str) -> Time {
-    let (hour_str, minute_str) = raw
-        .split_once(':')
-        .expect("Invalid time entry in schedules.bell");
-    let hour: u8 = hour_str.trim().parse().expect("Invalid hour in schedules.bell");
-    let minute: u8 = minute_str.trim().parse().expect("Invalid minute in schedules.bell");
-    Time::from_hms(hour, minute, 0).expect("Invalid time in schedules.bell")
+fn verify_special_days(calendar: &Calendar) {
+    for (idx, first) in calendar.special.iter().enumerate() {
+        let first_end = first.until.unwrap_or(first.on);
+        if first_end < first.on {
+            panic!("Special day range ends before it starts: {}", first.on);
+        }
+        for second in &calendar.special[idx + 1..] {
+            let second_end = second.until.unwrap_or(second.on);
+            let overlaps = first.on <= second_end && second.on <= first_end;
+            if overlaps
+                && first.schedule.is_some()
+                && second.schedule.is_some()
+                && special_day_span(first) == special_day_span(second)
+                && first.schedule != second.schedule
+            {
+                println!(
+                    "cargo:warning=Special days starting {} and {} overlap with equal precedence; the earlier entry wins",
+                    first.on, second.on
+                );
+            }
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Calendar {
-    pub default: Week,
-    pub special: Vec<SpecialDay>,
+fn verify_day_window(meta: &Meta, schedules: &ScheduleStore) {
+    let parse_bound = |raw: &Option<String>| {
+        raw.as_deref().map(|raw| {
+            parse::parse_start_time(raw).unwrap_or_else(|err| panic!("{} in meta.json", err))
+        })
+    };
+    let day_start = parse_bound(&meta.day_start);
+    let day_end = parse_bound(&meta.day_end);
+    if day_start.is_none() && day_end.is_none() {
+        return;
+    }
+    let time_format = format_description!("[hour]:[minute]");
+    let mut names: Vec<&String> = schedules.schedules.keys().collect();
+    names.sort();
+    for name in names {
+        for period in &schedules.schedules[name].periods {
+            let too_early = day_start.is_some_and(|start| period.start < start);
+            let too_late = day_end.is_some_and(|end| period.start > end);
+            if too_early || too_late {
+                println!(
+                    "cargo:warning=Period '{}' in schedule '{}' starts at {}, outside the school day",
+                    period.msg,
+                    name,
+                    period.start.format(time_format).unwrap()
+                );
+            }
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Default)]
-pub struct Week {
-    mon: Option<String>,
-    tue: Option<String>,
-    wed: Option<String>,
-    thu: Option<String>,
-    fri: Option<String>,
-    sat: Option<String>,
-    sun: Option<String>,
+fn report_default_week_runs(meta: &Meta, calendars: &[Calendar], schedules: &ScheduleStore) {
+    let (Some(start), Some(end)) = (meta.year_start, meta.year_end) else {
+        println!("cargo:warning=REPORT_CALENDAR_GAPS needs year_start and year_end in meta.json");
+        return;
+    };
+    let date_format = format_description!("[month]/[day]/[year]");
+    let mut runs: Vec<(Date, Date, usize)> = Vec::new();
+    let mut current: Option<(Date, Date, usize)> = None;
+    let mut date = start;
+    while date <= end {
+        let special = calendars
+            .iter()
+            .flat_map(|calendar| &calendar.special)
+            .filter(|special| special.schedule.is_some())
+            .any(|special| date >= special.on && date <= special.until.unwrap_or(special.on));
+        let has_periods = calendars
+            .iter()
+            .find_map(|calendar| calendar.default.schedule_for(date.weekday()))
+            .and_then(|name| schedules.schedules.get(name))
+            .is_some_and(|schedule| !schedule.periods.is_empty());
+        if special {
+            runs.extend(current.take());
+        } else if has_periods {
+            let run = current.get_or_insert((date, date, 0));
+            run.1 = date;
+            run.2 += 1;
+        }
+        match date.next_day() {
+            Some(next) => date = next,
+            None => break,
+        }
+    }
+    runs.extend(current.take());
+    for (first, last, days) in runs {
+        println!(
+            "cargo:warning=Default week from {} to {} ({} school days)",
+            first.format(date_format).unwrap(),
+            last.format(date_format).unwrap(),
+            days
+        );
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct SpecialDay {
-    on: Date,
-    until: Option<Date>,
-    schedule: String,
-    comment: Option<String>,
+fn special_day_span(special: &SpecialDay) -> i64 {
+    special
+        .until
+        .map_or(0, |until| (until - special.on).whole_days())
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct ScheduleStore {
-    pub schedules: HashMap<String, Schedule>,
+fn read_meta(meta_path: &Path) -> Meta {
+    let meta_data = std::fs::read_to_string(meta_path).expect("Failed to read meta.json");
+    parse::parse_meta(&meta_data).unwrap_or_else(|err| panic!("{}", err))
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Schedule {
-    pub comment: Option<String>,
-    pub periods: Vec<Period>,
+// calendar.bell is required, the class and district layers are optional.
+fn read_calendars(schedule: &Path) -> Vec<Calendar> {
+    let mut calendars = Vec::new();
+    for layer in CALENDAR_LAYERS {
+        let path = schedule.join(layer);
+        if *layer != "calendar.bell" && !path.exists() {
+            continue;
+        }
+        let buf = std::fs::read_to_string(&path)
+            .unwrap_or_else(|err| panic!("Failed to read {}: {}", layer, err));
+        let calendar =
+            parse::parse_calendar(&buf).unwrap_or_else(|err| panic!("{} in {}", err, layer));
+        calendars.push(calendar);
+    }
+    calendars
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Period {
-    pub msg: String,
-    pub start: Time,
+fn read_schedules(schedules_path: &Path) -> ScheduleStore {
+    let buf = std::fs::read_to_string(schedules_path).expect("Failed to read schedules.bell");
+    parse::parse_schedules(&buf).unwrap_or_else(|err| panic!("{}", err))
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AppData {
     pub meta: Meta,
-    pub calendar: Calendar,
+    pub calendars: Vec<Calendar>,
     pub schedules: ScheduleStore,
 }
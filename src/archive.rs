@@ -0,0 +1,99 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+
+use flate2::read::GzDecoder;
+
+use crate::model::CALENDAR_LAYERS;
+
+pub fn is_archive(path: &Path) -> bool {
+    let name = path.to_string_lossy();
+    [".tar.gz", ".tgz", ".zip"]
+        .iter()
+        .any(|suffix| name.ends_with(suffix))
+}
+
+// The files of a schedule bundle by file name. Directories inside the archive are
+// ignored, so a bundle may keep everything under a top-level folder. Only the data
+// files are read; anything else (a README, a logo) is skipped unread.
+pub fn read_archive(path: &Path) -> Result<HashMap<String, String>, String> {
+    let fail = |err: &dyn std::fmt::Display| format!("Failed to read {}: {err}", path.display());
+    let file = File::open(path).map_err(|err| fail(&err))?;
+    let mut files = HashMap::new();
+    if path.to_string_lossy().ends_with(".zip") {
+        let mut zip = zip::ZipArchive::new(BufReader::new(file)).map_err(|err| fail(&err))?;
+        for idx in 0..zip.len() {
+            let mut entry = zip.by_index(idx).map_err(|err| fail(&err))?;
+            if !entry.is_file() {
+                continue;
+            }
+            let Some(name) = file_name(entry.name()).filter(|name| is_data_file(name)) else {
+                continue;
+            };
+            let mut buf = String::new();
+            entry.read_to_string(&mut buf).map_err(|err| fail(&err))?;
+            files.insert(name, buf);
+        }
+        return Ok(files);
+    }
+    let mut tar = tar::Archive::new(GzDecoder::new(BufReader::new(file)));
+    for entry in tar.entries().map_err(|err| fail(&err))? {
+        let mut entry = entry.map_err(|err| fail(&err))?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path().map_err(|err| fail(&err))?;
+        let Some(name) = file_name(&path.to_string_lossy()).filter(|name| is_data_file(name))
+        else {
+            continue;
+        };
+        let mut buf = String::new();
+        entry.read_to_string(&mut buf).map_err(|err| fail(&err))?;
+        files.insert(name, buf);
+    }
+    Ok(files)
+}
+
+fn is_data_file(name: &str) -> bool {
+    matches!(name, "meta.json" | "schedules.bell") || CALENDAR_LAYERS.contains(&name)
+}
+
+fn file_name(path: &str) -> Option<String> {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use flate2::{Compression, write::GzEncoder};
+
+    use super::*;
+
+    #[test]
+    fn other_files_are_not_read() {
+        let path = std::env::temp_dir().join(format!("bell-{}.tar.gz", std::process::id()));
+        let mut tar = tar::Builder::new(GzEncoder::new(
+            File::create(&path).unwrap(),
+            Compression::fast(),
+        ));
+        for (name, data) in [
+            ("school/meta.json", b"{}".as_slice()),
+            ("school/logo.png", b"\x89PNG\xff\xfe".as_slice()),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_cksum();
+            tar.append_data(&mut header, name, data).unwrap();
+        }
+        tar.into_inner().unwrap().finish().unwrap();
+        let files = read_archive(&path);
+        std::fs::remove_file(&path).unwrap();
+        let files = files.unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files["meta.json"], "{}");
+    }
+}
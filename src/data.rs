@@ -1,67 +1,60 @@
-use std::collections::HashMap;
+use std::{
+    path::{Path, PathBuf},
+    sync::Once,
+};
 
 use serde::{Deserialize, Serialize};
-use time::{Date, Time, Weekday};
+use time::{Date, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset, Weekday};
+use time_tz::{OffsetDateTimeExt, PrimitiveDateTimeExt, Tz};
+
+use crate::error::AppError;
+pub use crate::model::{
+    CALENDAR_LAYERS, Calendar, Meta, Period, PeriodInsert, PeriodOverride, Schedule, ScheduleStore,
+    SpecialDay, Week, WeekStart,
+};
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AppData {
     pub meta: Meta,
-    pub calendar: Calendar,
+    pub calendars: Vec<Calendar>,
     pub schedules: ScheduleStore,
+    #[serde(skip)]
+    pub boundary: Boundary,
+    #[serde(skip)]
+    pub merge_adjacent: bool,
+    // Shifts the clock for --at.
+    #[serde(skip)]
+    pub clock_offset: time::Duration,
+    // Set by --override or the override file; replaces today's schedule only.
+    #[serde(skip)]
+    pub override_schedule: Option<String>,
+    // Days searched for the next bell (--max-lookahead); None uses the built-in bound.
+    #[serde(skip)]
+    pub max_lookahead: Option<u32>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Meta {
-    pub name: String,
-    pub periods: Vec<String>,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Calendar {
-    pub default: Week,
-    pub special: Vec<SpecialDay>,
-}
-
-#[derive(Serialize, Deserialize, Debug, Default)]
-pub struct Week {
-    pub mon: Option<String>,
-    pub tue: Option<String>,
-    pub wed: Option<String>,
-    pub thu: Option<String>,
-    pub fri: Option<String>,
-    pub sat: Option<String>,
-    pub sun: Option<String>,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-pub struct SpecialDay {
-    pub on: Date,
-    pub until: Option<Date>,
-    pub schedule: String,
-    pub comment: Option<String>,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-pub struct ScheduleStore {
-    pub schedules: HashMap<String, Schedule>,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-pub struct Schedule {
-    pub comment: Option<String>,
-    pub periods: Vec<Period>,
+// Whether the exact moment of a bell already belongs to the period that starts there.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Boundary {
+    #[default]
+    Inclusive,
+    Exclusive,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct Period {
-    pub msg: String,
-    pub start: Time,
+impl Boundary {
+    pub fn has_started(self, start: Time, time: Time) -> bool {
+        match self {
+            Boundary::Inclusive => time >= start,
+            Boundary::Exclusive => time > start,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CurrentSection {
     pub schedule_name: String,
     pub schedule_comment: Option<String>,
+    pub zone: Option<String>,
     pub current_period: Period,
     pub next_period: Option<Period>,
     pub current_period_end: Option<Time>,
@@ -69,48 +62,319 @@ pub struct CurrentSection {
     pub meta_periods: Vec<String>,
 }
 
+const BUILTIN_ICONS: &[(&str, &str)] = &[("lunch", "🍽"), ("brunch", "🍽"), ("passing", "🚶")];
+const BUILTIN_DEFAULT_ICON: &str = "📚";
+
 pub fn load_app_data() -> AppData {
     let bytes = include_bytes!(concat!(env!("OUT_DIR"), "/data.postcard"));
-    postcard::from_bytes(bytes).expect("Failed to deserialize data.postcard")
+    let payload = crate::model::data_payload(bytes)
+        .unwrap_or_else(|err| panic!("Failed to load data.postcard: {err}"));
+    postcard::from_bytes(payload).expect("Failed to deserialize data.postcard")
+}
+
+// Checked in order: $XDG_CONFIG_HOME/bell (or ~/.config/bell), then /etc/bell.
+// A directory only counts when it holds a meta.json.
+pub fn find_data_dir() -> Option<PathBuf> {
+    config_dirs().find(|dir| dir.join("meta.json").is_file())
+}
+
+// Schools live in subdirectories of the same locations, e.g. /etc/bell/lahs.
+pub fn find_school_dir(school: &str) -> Option<PathBuf> {
+    config_dirs()
+        .map(|dir| dir.join(school))
+        .find(|dir| dir.join("meta.json").is_file())
+}
+
+pub fn available_schools() -> Vec<String> {
+    let mut schools: Vec<String> = config_dirs()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().join("meta.json").is_file())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    schools.sort();
+    schools.dedup();
+    schools
+}
+
+// The state file holds nothing but the name of the selected school.
+pub fn default_school_file() -> Option<PathBuf> {
+    let state_home = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")));
+    state_home.map(|dir| dir.join("bell").join("school"))
+}
+
+pub fn read_school_file(path: &Path) -> Option<String> {
+    let school = std::fs::read_to_string(path).ok()?;
+    let school = school.trim();
+    (!school.is_empty()).then(|| school.to_string())
+}
+
+fn config_dirs() -> impl Iterator<Item = PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+    config_home
+        .map(|dir| dir.join("bell"))
+        .into_iter()
+        .chain([PathBuf::from("/etc/bell")])
+}
+
+pub fn timezone_by_name(name: &str) -> Option<&'static Tz> {
+    time_tz::timezones::get_by_name(name)
 }
 
 impl AppData {
+    // Runs the parsers straight on a schedule directory, the same files build.rs reads,
+    // so neither a rebuild nor the embedded blob is involved.
+    pub fn from_schedule_dir(dir: &Path) -> Result<AppData, AppError> {
+        Self::from_files(
+            |name| {
+                std::fs::read_to_string(dir.join(name)).map_err(|err| {
+                    AppError::Io(format!(
+                        "Failed to read {}: {err}",
+                        dir.join(name).display()
+                    ))
+                })
+            },
+            |name| dir.join(name).is_file(),
+        )
+    }
+
+    // The same files from a .tar.gz or .zip bundle, unpacked in memory.
+    #[cfg(feature = "archive")]
+    pub fn from_archive(path: &Path) -> Result<AppData, AppError> {
+        let files = crate::archive::read_archive(path).map_err(AppError::Io)?;
+        Self::from_files(
+            |name| {
+                files
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| AppError::Io(format!("{} has no {name}", path.display())))
+            },
+            |name| files.contains_key(name),
+        )
+    }
+
+    fn from_files(
+        read: impl Fn(&str) -> Result<String, AppError>,
+        exists: impl Fn(&str) -> bool,
+    ) -> Result<AppData, AppError> {
+        let meta = crate::parse::parse_meta(&read("meta.json")?)
+            .map_err(|err| AppError::Parse(err.to_string()))?;
+        let mut calendars = Vec::new();
+        for layer in CALENDAR_LAYERS {
+            if *layer != "calendar.bell" && !exists(layer) {
+                continue;
+            }
+            let calendar = crate::parse::parse_calendar(&read(layer)?)
+                .map_err(|err| AppError::Parse(format!("{err} in {layer}")))?;
+            calendars.push(calendar);
+        }
+        let schedules = crate::parse::parse_schedules(&read("schedules.bell")?)
+            .map_err(|err| AppError::Parse(err.to_string()))?;
+        crate::parse::check_inserts(&meta, &calendars, &schedules)
+            .map_err(|err| AppError::Parse(err.to_string()))?;
+        Ok(AppData {
+            meta,
+            calendars,
+            schedules,
+            boundary: Boundary::default(),
+            merge_adjacent: false,
+            clock_offset: time::Duration::ZERO,
+            override_schedule: None,
+            max_lookahead: None,
+        })
+    }
+
+    pub fn now(&self) -> OffsetDateTime {
+        let utc = OffsetDateTime::now_utc() + self.clock_offset;
+        match self.meta.timezone.as_deref().and_then(timezone_by_name) {
+            Some(tz) => utc.to_timezone(tz),
+            None => match UtcOffset::current_local_offset() {
+                Ok(offset) => utc.to_offset(offset),
+                Err(_) => {
+                    static WARNED: Once = Once::new();
+                    WARNED.call_once(|| {
+                        log::warn!("Local UTC offset unavailable, falling back to UTC")
+                    });
+                    utc
+                }
+            },
+        }
+    }
+
+    pub fn in_school_year(&self, date: Date) -> bool {
+        self.meta.year_start.is_none_or(|start| date >= start)
+            && self.meta.year_end.is_none_or(|end| date <= end)
+    }
+
+    fn override_for(&self, date: Date) -> Option<&str> {
+        self.override_schedule
+            .as_deref()
+            .filter(|_| date == self.now().date())
+    }
+
     pub fn schedule_name_for_date(&self, date: Date) -> Option<&str> {
-        for special in &self.calendar.special {
-            if is_special_day_match(date, special) {
-                return Some(special.schedule.as_str());
+        if let Some(name) = self.override_for(date) {
+            return Some(name);
+        }
+        if !self.in_school_year(date) {
+            return None;
+        }
+        if let Some(special) = self.special_day_for_date(date) {
+            return special.schedule.as_deref();
+        }
+        self.calendars
+            .iter()
+            .find_map(|calendar| calendar.default.schedule_for(date.weekday()))
+    }
+
+    // Layers are searched most specific first and the first layer with a match decides.
+    // Within a layer a single day beats any range and shorter ranges beat longer ones.
+    // Ties keep file order.
+    pub fn special_day_for_date(&self, date: Date) -> Option<&SpecialDay> {
+        self.calendars.iter().find_map(|calendar| {
+            calendar
+                .special
+                .iter()
+                .filter(|special| special.schedule.is_some() && is_special_day_match(date, special))
+                .min_by_key(|special| special_day_span(special))
+        })
+    }
+
+    // Notes without a schedule take part here, so "Spirit Week" shows on regular days.
+    pub fn day_comment(&self, date: Date) -> Option<&str> {
+        self.calendars.iter().find_map(|calendar| {
+            calendar
+                .special
+                .iter()
+                .filter(|special| special.comment.is_some() && is_special_day_match(date, special))
+                .min_by_key(|special| special_day_span(special))
+                .and_then(|special| special.comment.as_deref())
+        })
+    }
+
+    pub fn iter_days(&self, start: Date, days: usize) -> impl Iterator<Item = (Date, Vec<Period>)> {
+        std::iter::successors(Some(start), |date| date.next_day())
+            .take(days)
+            .map(|date| (date, self.visible_periods(date).unwrap_or_default()))
+    }
+
+    pub fn week_start_date(&self, date: Date) -> Date {
+        let first_day = match self.meta.week_start {
+            WeekStart::Monday => Weekday::Monday,
+            WeekStart::Sunday => Weekday::Sunday,
+        };
+        let mut start = date;
+        while start.weekday() != first_day {
+            start = start.previous_day().unwrap_or(start);
+        }
+        start
+    }
+
+    pub fn periods_for_date(&self, date: Date) -> Option<Vec<Period>> {
+        let (mut periods, _) = self.scheduled_day(date)?;
+        // Back-to-back entries with the same message count down as one block.
+        if self.merge_adjacent {
+            periods.dedup_by(|later, earlier| later.msg == earlier.msg);
+        }
+        Some(periods)
+    }
+
+    // The periods that are shown; hidden markers only move the boundaries.
+    pub fn visible_periods(&self, date: Date) -> Option<Vec<Period>> {
+        let mut periods = self.periods_for_date(date)?;
+        periods.retain(|period| !period.hidden);
+        Some(periods)
+    }
+
+    // The day's periods and the end of the last one (the schedule's "last_end", else
+    // meta.json "day_end"), with the special day's overrides and inserts applied.
+    fn scheduled_day(&self, date: Date) -> Option<(Vec<Period>, Option<Time>)> {
+        let schedule_name = self.schedule_name_for_date(date)?;
+        let schedule = self.schedules.schedules.get(schedule_name)?;
+        let periods = schedule.periods.clone();
+        let last_end = schedule.last_end.or_else(|| {
+            let raw = self.meta.day_end.as_deref()?;
+            crate::parse::parse_start_time(raw).ok()
+        });
+        if self.override_for(date).is_some() {
+            return Some((periods, last_end));
+        }
+        let Some(special) = self.special_day_for_date(date) else {
+            return Some((periods, last_end));
+        };
+        let (mut adjusted, mut adjusted_end) = (periods.clone(), last_end);
+        // Loading already rejects inserts past midnight, so this only guards odd data.
+        match crate::parse::apply_special_day(&mut adjusted, &mut adjusted_end, special) {
+            Ok(()) => Some((adjusted, adjusted_end)),
+            Err(err) => {
+                log::warn!("Ignoring the changes for {date}: {err}");
+                Some((periods, last_end))
             }
         }
-        let week = &self.calendar.default;
-        match date.weekday() {
-            Weekday::Monday => week.mon.as_deref(),
-            Weekday::Tuesday => week.tue.as_deref(),
-            Weekday::Wednesday => week.wed.as_deref(),
-            Weekday::Thursday => week.thu.as_deref(),
-            Weekday::Friday => week.fri.as_deref(),
-            Weekday::Saturday => week.sat.as_deref(),
-            Weekday::Sunday => week.sun.as_deref(),
+    }
+
+    // Reference point before the first bell: meta.json "day_start", else the first period.
+    pub fn day_start(&self, date: Date) -> Option<Time> {
+        if let Some(raw) = &self.meta.day_start {
+            return crate::parse::parse_start_time(raw).ok();
         }
+        self.periods_for_date(date)?
+            .first()
+            .map(|period| period.start)
+    }
+
+    // The schedule's "last_end", else meta.json "day_end", moved by any inserts.
+    pub fn last_end(&self, date: Date) -> Option<Time> {
+        self.scheduled_day(date)?.1
     }
 
     pub fn current_section(&self, date: Date, time: Time) -> Option<CurrentSection> {
         let schedule_name = self.schedule_name_for_date(date)?;
         let schedule = self.schedules.schedules.get(schedule_name)?;
+        let periods = self.periods_for_date(date)?;
         let mut current_index = None;
-        for (idx, period) in schedule.periods.iter().enumerate() {
-            if time >= period.start {
+        for (idx, period) in periods.iter().enumerate() {
+            if self.boundary.has_started(period.start, time) {
                 current_index = Some(idx);
             } else {
                 break;
             }
         }
         let current_index = current_index?;
-        let current_period = schedule.periods.get(current_index)?.clone();
-        let next_period = schedule.periods.get(current_index + 1).cloned();
-        let current_period_end = next_period.as_ref().map(|next| next.start);
+        let mut current_period = periods.get(current_index)?.clone();
+        // A hidden period keeps its start as the boundary but shows the last visible
+        // period before it, or nothing.
+        if current_period.hidden {
+            let shown = periods[..current_index]
+                .iter()
+                .rev()
+                .find(|period| !period.hidden);
+            current_period.msg = shown.map(|period| period.msg.clone()).unwrap_or_default();
+            current_period.comment = shown.and_then(|period| period.comment.clone());
+            current_period.rotation = shown
+                .map(|period| period.rotation.clone())
+                .unwrap_or_default();
+        }
+        // The period ends at the next boundary, but what comes next is the next visible one.
+        let next_period = periods[current_index + 1..]
+            .iter()
+            .find(|period| !period.hidden)
+            .cloned();
+        let current_period_end = match periods.get(current_index + 1) {
+            Some(next) => Some(next.start),
+            None => self
+                .last_end(date)
+                .filter(|end| *end > current_period.start),
+        };
         Some(CurrentSection {
             schedule_name: schedule_name.to_string(),
             schedule_comment: schedule.comment.clone(),
+            zone: schedule.zone.clone(),
             current_period,
             next_period,
             current_period_end,
@@ -118,6 +382,157 @@ impl AppData {
             meta_periods: self.meta.periods.clone(),
         })
     }
+
+    // From the first bell to the end of the last period, or to the last bell when the
+    // final period has no end.
+    pub fn day_bounds(&self, date: Date) -> Option<(Time, Time)> {
+        let periods = self.periods_for_date(date)?;
+        let (first, last) = (periods.first()?.start, periods.last()?.start);
+        let end = self.last_end(date).filter(|end| *end > last);
+        Some((first, end.unwrap_or(last)))
+    }
+
+    // Periods of the day with their ends whose [start, end) meets [start, end); spans that
+    // only touch do not overlap. The final entry counts only when it has a last_end.
+    pub fn periods_overlapping(&self, date: Date, start: Time, end: Time) -> Vec<(Period, Time)> {
+        self.period_spans(date)
+            .into_iter()
+            .filter(|(period, period_end)| period.start < end && start < *period_end)
+            .collect()
+    }
+
+    // Each period lasts until the next bell, the last one until last_end. Periods whose
+    // name contains one of the `exclude` keywords (case-insensitive, e.g. "lunch") are
+    // left out.
+    pub fn instructional_minutes(&self, date: Date, exclude: &[String]) -> i64 {
+        self.period_spans(date)
+            .into_iter()
+            .filter(|(period, _)| {
+                let msg = period.msg.to_lowercase();
+                !exclude
+                    .iter()
+                    .any(|keyword| msg.contains(&keyword.to_lowercase()))
+            })
+            .map(|(period, period_end)| (period_end - period.start).whole_minutes())
+            .sum()
+    }
+
+    // The visible periods with their ends; the final one only when it has a last_end.
+    fn period_spans(&self, date: Date) -> Vec<(Period, Time)> {
+        let periods = self.visible_periods(date).unwrap_or_default();
+        let last_end = self
+            .last_end(date)
+            .filter(|end| periods.last().is_some_and(|last| *end > last.start));
+        let ends: Vec<Option<Time>> = periods
+            .iter()
+            .skip(1)
+            .map(|next| Some(next.start))
+            .chain([last_end])
+            .collect();
+        periods
+            .into_iter()
+            .zip(ends)
+            .filter_map(|(period, period_end)| Some((period, period_end?)))
+            .collect()
+    }
+
+    // Passing periods count as in session; the end of the day itself does not.
+    pub fn in_session(&self, date: Date, time: Time) -> bool {
+        self.day_bounds(date)
+            .is_some_and(|(first, last)| time >= first && time < last)
+    }
+
+    // The schedule's comment is its display name; the key stands in when there is none.
+    pub fn schedule_title(&self, date: Date) -> Option<&str> {
+        let schedule_name = self.schedule_name_for_date(date)?;
+        let schedule = self.schedules.schedules.get(schedule_name)?;
+        Some(schedule.comment.as_deref().unwrap_or(schedule_name))
+    }
+
+    pub fn zone_for_date(&self, date: Date) -> Option<&str> {
+        let schedule_name = self.schedule_name_for_date(date)?;
+        self.schedules.schedules.get(schedule_name)?.zone.as_deref()
+    }
+
+    pub fn free_day_name(&self, date: Date) -> Option<&str> {
+        let schedule_name = self.schedule_name_for_date(date)?;
+        let schedule = self.schedules.schedules.get(schedule_name)?;
+        if !schedule.periods.is_empty() {
+            return None;
+        }
+        Some(schedule.comment.as_deref().unwrap_or(schedule_name))
+    }
+
+    // A wall-clock time on `date` as an instant, at the offset the schedule's timezone has
+    // on that day; without a timezone the current offset stands in.
+    pub fn local_datetime(&self, date: Date, time: Time) -> OffsetDateTime {
+        let local = PrimitiveDateTime::new(date, time);
+        self.meta
+            .timezone
+            .as_deref()
+            .and_then(timezone_by_name)
+            .and_then(|tz| local.assume_timezone(tz).take_first())
+            .unwrap_or_else(|| local.assume_offset(self.now().offset()))
+    }
+
+    // Weeks are numbered by ISO-8601 (Monday start, week 1 holds the first Thursday of
+    // the year). With a Sunday week start, Sunday joins the ISO week of the following Monday.
+    pub fn week_index(&self, date: Date) -> u8 {
+        match self.meta.week_start {
+            WeekStart::Monday => date.iso_week(),
+            WeekStart::Sunday if date.weekday() == Weekday::Sunday => {
+                date.next_day().unwrap_or(date).iso_week()
+            }
+            WeekStart::Sunday => date.iso_week(),
+        }
+    }
+
+    pub fn is_day_over(&self, date: Date, time: Time) -> bool {
+        self.current_section(date, time).is_some_and(|section| {
+            section.next_period.is_none()
+                && section.current_period_end.is_none_or(|end| time >= end)
+        })
+    }
+}
+
+impl Meta {
+    pub fn is_break(&self, msg: &str) -> bool {
+        self.breaks
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(msg))
+    }
+
+    pub fn icon_for(&self, msg: &str) -> &str {
+        let msg = msg.to_lowercase();
+        let configured = self
+            .icons
+            .iter()
+            .map(|(keyword, icon)| (keyword.as_str(), icon.as_str()));
+        let found = if self.icons.is_empty() {
+            longest_icon_match(&msg, BUILTIN_ICONS.iter().copied())
+        } else {
+            longest_icon_match(&msg, configured)
+        };
+        found
+            .or(self.default_icon.as_deref())
+            .unwrap_or(BUILTIN_DEFAULT_ICON)
+    }
+}
+
+fn longest_icon_match<'a, I>(msg: &str, table: I) -> Option<&'a str>
+where
+    I: Iterator<Item = (&'a str, &'a str)>,
+{
+    table
+        .filter(|(keyword, _)| msg.contains(&keyword.to_lowercase()))
+        .max_by_key(|(keyword, _)| keyword.len())
+        .map(|(_, icon)| icon)
+}
+
+fn special_day_span(special: &SpecialDay) -> i64 {
+    special
+        .until
+        .map_or(0, |until| (until - special.on).whole_days())
 }
 
 fn is_special_day_match(date: Date, special: &SpecialDay) -> bool {
@@ -126,3 +541,233 @@ fn is_special_day_match(date: Date, special: &SpecialDay) -> bool {
         None => date == special.on,
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use time::{
+        PrimitiveDateTime,
+        macros::{date, time},
+    };
+
+    use super::*;
+
+    // Schedule directories checked in under tests/fixtures, loaded like `--data`.
+    pub fn fixture(name: &str) -> AppData {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        AppData::from_schedule_dir(&dir).unwrap()
+    }
+
+    // Moves `now()` to a UTC instant, the fixtures' timezone.
+    pub fn pin_now(data: &mut AppData, at: PrimitiveDateTime) {
+        data.clock_offset = at.assume_utc() - OffsetDateTime::now_utc();
+    }
+
+    #[test]
+    fn fixture_loads_every_layer() {
+        let data = fixture("basic");
+        assert_eq!(data.meta.name, "Fixture High");
+        assert_eq!(data.calendars.len(), 2);
+        assert_eq!(data.schedules.schedules.len(), 5);
+    }
+
+    #[test]
+    fn current_section_mid_period() {
+        let data = fixture("basic");
+        let section = data
+            .current_section(date!(2026 - 10 - 12), time!(09:15))
+            .unwrap();
+        assert_eq!(section.schedule_name, "regular");
+        assert_eq!(section.current_period.msg, "Period 2");
+        assert_eq!(section.current_period_end, Some(time!(09:50)));
+        assert_eq!(section.next_period.unwrap().msg, "Lunch");
+    }
+
+    #[test]
+    fn current_section_on_a_bell() {
+        let mut data = fixture("basic");
+        let at_bell = |data: &AppData| {
+            data.current_section(date!(2026 - 10 - 12), time!(08:50))
+                .unwrap()
+                .current_period
+                .msg
+        };
+        assert_eq!(at_bell(&data), "Period 2");
+        data.boundary = Boundary::Exclusive;
+        assert_eq!(at_bell(&data), "Period 1");
+    }
+
+    #[test]
+    fn current_section_outside_the_day() {
+        let data = fixture("basic");
+        assert!(
+            data.current_section(date!(2026 - 10 - 12), time!(07:59))
+                .is_none()
+        );
+        // Saturday has no schedule in the default week.
+        assert!(
+            data.current_section(date!(2026 - 10 - 17), time!(09:00))
+                .is_none()
+        );
+        let last = data
+            .current_section(date!(2026 - 10 - 12), time!(15:00))
+            .unwrap();
+        assert_eq!(last.current_period.msg, "Free");
+        assert_eq!(last.current_period_end, None);
+        assert!(last.next_period.is_none());
+    }
+
+    #[test]
+    fn special_day_range_applies_inside_it() {
+        let data = fixture("basic");
+        assert_eq!(
+            data.schedule_name_for_date(date!(2026 - 12 - 14)),
+            Some("finals")
+        );
+        assert_eq!(
+            data.schedule_name_for_date(date!(2026 - 12 - 18)),
+            Some("finals")
+        );
+        assert_eq!(
+            data.schedule_name_for_date(date!(2026 - 12 - 21)),
+            Some("regular")
+        );
+    }
+
+    #[test]
+    fn single_special_day_beats_a_range_around_it() {
+        let data = fixture("basic");
+        // calendar.bell lists the finals range before the single assembly day.
+        assert_eq!(
+            data.schedule_name_for_date(date!(2026 - 12 - 16)),
+            Some("assembly")
+        );
+    }
+
+    #[test]
+    fn more_specific_layer_wins_over_a_single_day() {
+        let data = fixture("basic");
+        // district.bell's single day loses to calendar.bell's finals range.
+        assert_eq!(
+            data.schedule_name_for_date(date!(2026 - 12 - 17)),
+            Some("finals")
+        );
+        // Where calendar.bell has nothing, the district layer still applies.
+        assert_eq!(
+            data.schedule_name_for_date(date!(2026 - 11 - 26)),
+            Some("holiday")
+        );
+        assert_eq!(
+            data.free_day_name(date!(2026 - 11 - 26)),
+            Some("Thanksgiving")
+        );
+    }
+
+    #[test]
+    fn week_index_across_the_year_boundary() {
+        let data = fixture("basic");
+        // 2026 starts on a Thursday, so it has an ISO week 53 running into 2027.
+        assert_eq!(data.week_index(date!(2026 - 12 - 28)), 53);
+        assert_eq!(data.week_index(date!(2026 - 12 - 31)), 53);
+        assert_eq!(data.week_index(date!(2027 - 01 - 01)), 53);
+        assert_eq!(data.week_index(date!(2027 - 01 - 03)), 53);
+        assert_eq!(data.week_index(date!(2027 - 01 - 04)), 1);
+    }
+
+    #[test]
+    fn week_index_with_a_sunday_week_start() {
+        let mut data = fixture("basic");
+        data.meta.week_start = WeekStart::Sunday;
+        // Sundays move to the week of the following Monday; other days keep ISO weeks.
+        assert_eq!(data.week_index(date!(2026 - 12 - 27)), 53);
+        assert_eq!(data.week_index(date!(2027 - 01 - 03)), 1);
+        assert_eq!(data.week_index(date!(2027 - 01 - 01)), 53);
+        assert_eq!(data.week_index(date!(2027 - 01 - 04)), 1);
+    }
+
+    fn overlapping(data: &AppData, start: Time, end: Time) -> Vec<String> {
+        data.periods_overlapping(date!(2026 - 10 - 14), start, end)
+            .into_iter()
+            .map(|(period, _)| period.msg)
+            .collect()
+    }
+
+    #[test]
+    fn periods_overlapping_ignores_touching_spans() {
+        let data = fixture("basic");
+        // Period 1 ends and Lunch starts exactly on the edges of Period 2.
+        assert_eq!(overlapping(&data, time!(08:50), time!(09:50)), ["Period 2"]);
+        assert_eq!(
+            overlapping(&data, time!(08:30), time!(10:00)),
+            ["Period 1", "Period 2", "Lunch"]
+        );
+    }
+
+    #[test]
+    fn periods_overlapping_with_a_nested_span() {
+        let data = fixture("basic");
+        assert_eq!(overlapping(&data, time!(09:10), time!(09:20)), ["Period 2"]);
+        // The final entry has no end, so it never overlaps.
+        assert!(overlapping(&data, time!(11:30), time!(12:00)).is_empty());
+    }
+
+    #[test]
+    fn hidden_period_keeps_the_boundary_but_shows_the_previous_one() {
+        let data = fixture("basic");
+        let drill = date!(2026 - 10 - 21);
+        let before = data.current_section(drill, time!(08:20)).unwrap();
+        assert_eq!(before.current_period_end, Some(time!(08:30)));
+        assert_eq!(before.next_period.unwrap().msg, "Period 2");
+        let during = data.current_section(drill, time!(08:40)).unwrap();
+        assert_eq!(during.current_period.msg, "Period 1");
+        assert_eq!(during.current_period_end, Some(time!(08:50)));
+        let shown: Vec<_> = data
+            .visible_periods(drill)
+            .unwrap()
+            .into_iter()
+            .map(|period| period.msg)
+            .collect();
+        assert_eq!(shown, ["Period 1", "Period 2", "Free"]);
+    }
+
+    #[test]
+    fn last_end_closes_the_day() {
+        let data = fixture("basic");
+        let finals = date!(2026 - 12 - 14);
+        assert_eq!(data.day_bounds(finals), Some((time!(09:00), time!(13:00))));
+        assert!(data.in_session(finals, time!(12:30)));
+        assert!(!data.in_session(finals, time!(13:00)));
+        // Without a last_end the last bell ends the day.
+        let regular = date!(2026 - 10 - 14);
+        assert_eq!(data.day_bounds(regular), Some((time!(08:00), time!(11:20))));
+    }
+
+    #[test]
+    fn instructional_minutes_count_the_last_period_to_last_end() {
+        let data = fixture("basic");
+        let finals = date!(2026 - 12 - 14);
+        assert_eq!(data.instructional_minutes(finals, &[]), 240);
+        assert_eq!(
+            data.instructional_minutes(finals, &["free".to_string()]),
+            180
+        );
+        // Without a last_end the final entry only marks the end of the day.
+        let regular = date!(2026 - 10 - 14);
+        assert_eq!(data.instructional_minutes(regular, &[]), 200);
+        assert_eq!(
+            data.instructional_minutes(regular, &["lunch".to_string()]),
+            160
+        );
+    }
+
+    #[test]
+    fn insert_after_the_last_period_moves_the_end() {
+        let data = fixture("basic");
+        let awards = date!(2027 - 01 - 06);
+        let periods = data.periods_for_date(awards).unwrap();
+        assert_eq!(periods.last().unwrap().msg, "Awards");
+        assert_eq!(data.last_end(awards), Some(time!(13:30)));
+        assert_eq!(data.day_bounds(awards), Some((time!(09:00), time!(13:30))));
+    }
+}
@@ -0,0 +1,35 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum AppError {
+    // --help; not a failure, main prints the usage and exits 0.
+    Help,
+    BadArgs(String),
+    NoSchool(String),
+    Io(String),
+    Parse(String),
+    Other(String),
+}
+
+impl AppError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AppError::Help => 0,
+            AppError::BadArgs(_) => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Help => f.write_str("Requested help."),
+            AppError::BadArgs(msg)
+            | AppError::NoSchool(msg)
+            | AppError::Io(msg)
+            | AppError::Parse(msg)
+            | AppError::Other(msg) => f.write_str(msg),
+        }
+    }
+}
@@ -0,0 +1,314 @@
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+use time::{Date, Weekday, format_description::well_known::Rfc3339, macros::format_description};
+
+use crate::data::AppData;
+
+// With `around`, only the current period (or, before it starts, the next one) and
+// that many periods on either side are listed.
+pub fn day_listing(data: &AppData, date: Date, color: bool, around: Option<usize>) -> String {
+    let header_format = format_description!("[weekday repr:short] [year]-[month]-[day]");
+    let mut out = date.format(header_format).unwrap();
+    let schedule = data
+        .schedule_name_for_date(date)
+        .and_then(|name| Some((name, data.schedules.schedules.get(name)?)));
+    match schedule {
+        Some((name, schedule)) => {
+            out.push_str(": ");
+            out.push_str(schedule.comment.as_deref().unwrap_or(name));
+        }
+        None => out.push_str(": No school"),
+    }
+    out.push('\n');
+    let now = data.now();
+    let periods = data.visible_periods(date).unwrap_or_default();
+    let today = now.date() == date;
+    let current = today
+        .then(|| data.current_section(date, now.time()))
+        .flatten()
+        .and_then(|section| {
+            // A hidden period stands for the visible one before it.
+            let current = section.current_period;
+            periods
+                .iter()
+                .rposition(|period| period.start <= current.start)
+        });
+    let anchor = current.or_else(|| {
+        today
+            .then(|| periods.iter().position(|period| period.start > now.time()))
+            .flatten()
+    });
+    let shown = match (around, anchor) {
+        (Some(around), Some(anchor)) => {
+            anchor.saturating_sub(around)..(anchor + around + 1).min(periods.len())
+        }
+        _ => 0..periods.len(),
+    };
+    for (idx, period) in periods.iter().enumerate() {
+        if !shown.contains(&idx) {
+            continue;
+        }
+        let line = format!("{} {}", crate::clock_time(period.start), period.msg);
+        if color && current == Some(idx) {
+            out.push_str(&format!("\x1b[1;7m{line}\x1b[0m\n"));
+        } else if color && current.is_some_and(|current| idx < current) {
+            out.push_str(&format!("\x1b[2m{line}\x1b[0m\n"));
+        } else {
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+// The day ends with the last period's end, or with the final bell when it has none.
+pub fn day_summary(data: &AppData, date: Date) -> String {
+    let Some((first, last)) = data.day_bounds(date) else {
+        if date == data.now().date() {
+            return "No school today.\n".to_string();
+        }
+        let day_format = format_description!("[weekday repr:short] [year]-[month]-[day]");
+        return format!("No school on {}.\n", date.format(day_format).unwrap());
+    };
+    let name = data
+        .schedule_name_for_date(date)
+        .map(|name| {
+            data.schedules
+                .schedules
+                .get(name)
+                .and_then(|schedule| schedule.comment.as_deref())
+                .unwrap_or(name)
+        })
+        .unwrap_or_default();
+    let zone = data
+        .zone_for_date(date)
+        .map(|zone| format!(" ({zone})"))
+        .unwrap_or_default();
+    format!(
+        "{}{}: {} - {}\n",
+        name,
+        zone,
+        crate::clock_time(first),
+        crate::clock_time(last)
+    )
+}
+
+#[derive(Serialize)]
+struct DayPeriod<'a> {
+    start: String,
+    end: Option<String>,
+    msg: &'a str,
+}
+
+// Each period ends where the next one starts; the final bell has no end.
+pub fn day_json(data: &AppData, date: Date) -> String {
+    let periods = data.visible_periods(date).unwrap_or_default();
+    let entries: Vec<DayPeriod> = periods
+        .iter()
+        .enumerate()
+        .map(|(idx, period)| DayPeriod {
+            start: crate::clock_time(period.start),
+            end: periods
+                .get(idx + 1)
+                .map(|next| next.start)
+                .or_else(|| data.last_end(date))
+                .map(crate::clock_time),
+            msg: &period.msg,
+        })
+        .collect();
+    serde_json::to_string_pretty(&entries).expect("Failed to serialize day schedule")
+}
+
+// Every bell of the day as an RFC 3339 timestamp and the event it marks. A bell both
+// ends one period and starts the next; the final bell only ends the last period,
+// unless the schedule sets a last_end for it. Names are slugged so that every line
+// splits into exactly two fields.
+pub fn day_transitions(data: &AppData, date: Date) -> String {
+    let periods = data.visible_periods(date).unwrap_or_default();
+    let last_end = data
+        .last_end(date)
+        .filter(|end| periods.last().is_some_and(|last| *end > last.start));
+    let stamp = |time| data.local_datetime(date, time).format(&Rfc3339).unwrap();
+    let slug = |msg: &str| msg.split_whitespace().collect::<Vec<_>>().join("_");
+    let mut out = String::new();
+    for (idx, period) in periods.iter().enumerate() {
+        let at = stamp(period.start);
+        if let Some(previous) = idx.checked_sub(1).map(|prev| &periods[prev]) {
+            out.push_str(&format!("{at} end_of_{}\n", slug(&previous.msg)));
+        }
+        if idx + 1 < periods.len() || last_end.is_some() {
+            out.push_str(&format!("{at} start_of_{}\n", slug(&period.msg)));
+        }
+    }
+    if let (Some(end), Some(last)) = (last_end, periods.last()) {
+        out.push_str(&format!("{} end_of_{}\n", stamp(end), slug(&last.msg)));
+    }
+    out
+}
+
+// One cluster per school day of the week, each period a node chained to the next
+// in bell order.
+pub fn weekly_dot(data: &AppData, week_of: Date) -> String {
+    let start = data.week_start_date(week_of);
+    let header_format = format_description!("[weekday repr:short] [month]/[day]");
+    let id_format = format_description!("[year][month][day]");
+    let mut out = String::from("digraph week {\n    rankdir=LR;\n    node [shape=box];\n");
+    for (date, periods) in data.iter_days(start, 7) {
+        if periods.is_empty() {
+            continue;
+        }
+        let id = date.format(id_format).unwrap();
+        let mut title = date.format(header_format).unwrap();
+        if let Some(name) = data.schedule_title(date) {
+            title = format!("{title} ({name})");
+        }
+        out.push_str(&format!("    subgraph cluster_{id} {{\n"));
+        out.push_str(&format!("        label=\"{}\";\n", dot_escape(&title)));
+        for (idx, period) in periods.iter().enumerate() {
+            let label = format!(
+                "{}\\n{}",
+                crate::clock_time(period.start),
+                dot_escape(&period.msg)
+            );
+            out.push_str(&format!("        d{id}_{idx} [label=\"{label}\"];\n"));
+        }
+        for idx in 1..periods.len() {
+            out.push_str(&format!("        d{id}_{} -> d{id}_{idx};\n", idx - 1));
+        }
+        out.push_str("    }\n");
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+pub fn weekly_table(data: &AppData, week_of: Date) -> String {
+    let start = data.week_start_date(week_of);
+    let days: Vec<_> = data
+        .iter_days(start, 7)
+        .filter(|(date, periods)| {
+            !periods.is_empty() || !matches!(date.weekday(), Weekday::Saturday | Weekday::Sunday)
+        })
+        .collect();
+    let slots: BTreeSet<_> = days
+        .iter()
+        .flat_map(|(_, periods)| periods.iter().map(|period| period.start))
+        .collect();
+
+    let header_format = format_description!("[weekday repr:short] [month]/[day]");
+    let mut rows = Vec::with_capacity(slots.len() + 1);
+    let mut header = vec![String::new()];
+    for (date, periods) in &days {
+        let mut title = date.format(header_format).unwrap();
+        if periods.is_empty()
+            && let Some(name) = data.free_day_name(*date)
+        {
+            title = format!("{title} ({name})");
+        }
+        header.push(title);
+    }
+    rows.push(header);
+    for slot in &slots {
+        let mut row = vec![crate::clock_time(*slot)];
+        for (_, periods) in &days {
+            let cell = periods
+                .iter()
+                .find(|period| period.start == *slot)
+                .map(|period| period.msg.clone())
+                .unwrap_or_default();
+            row.push(cell);
+        }
+        rows.push(row);
+    }
+
+    let columns = days.len() + 1;
+    let widths: Vec<usize> = (0..columns)
+        .map(|col| {
+            rows.iter()
+                .map(|row| row[col].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let mut out = String::new();
+    for (idx, row) in rows.iter().enumerate() {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+        out.push_str(line.join(" | ").trim_end());
+        out.push('\n');
+        if idx == 0 {
+            let rule: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+            out.push_str(&rule.join("-+-"));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{date, datetime};
+
+    use super::*;
+    use crate::data::tests::{fixture, pin_now};
+
+    #[test]
+    fn hidden_periods_are_left_out() {
+        let data = fixture("basic");
+        let drill = date!(2026 - 10 - 21);
+        assert_eq!(
+            day_listing(&data, drill, false, None),
+            "Wed 2026-10-21: Drill Day\n08:00 Period 1\n08:50 Period 2\n09:50 Free\n"
+        );
+        assert!(!day_transitions(&data, drill).contains("Bell test"));
+        let json = day_json(&data, drill);
+        assert!(!json.contains("Bell test"));
+        assert!(json.contains("\"end\": \"08:50\""));
+    }
+
+    #[test]
+    fn last_end_reaches_summary_and_transitions() {
+        let data = fixture("basic");
+        let finals = date!(2026 - 12 - 14);
+        assert_eq!(day_summary(&data, finals), "Finals Week: 09:00 - 13:00\n");
+        assert_eq!(
+            day_transitions(&data, finals),
+            "2026-12-14T09:00:00Z start_of_Finals\n\
+             2026-12-14T12:00:00Z end_of_Finals\n\
+             2026-12-14T12:00:00Z start_of_Free\n\
+             2026-12-14T13:00:00Z end_of_Free\n"
+        );
+    }
+
+    #[test]
+    fn transitions_follow_the_timezone_of_each_date() {
+        let mut data = fixture("basic");
+        data.meta.timezone = Some("America/Los_Angeles".to_string());
+        let summer = day_transitions(&data, date!(2026 - 10 - 14));
+        assert!(summer.starts_with("2026-10-14T08:00:00-07:00 start_of_Period_1\n"));
+        let winter = day_transitions(&data, date!(2026 - 12 - 14));
+        assert!(winter.starts_with("2026-12-14T09:00:00-08:00 start_of_Finals\n"));
+        assert!(winter.lines().all(|line| line.split(' ').count() == 2));
+    }
+
+    #[test]
+    fn summary_without_school() {
+        let mut data = fixture("basic");
+        pin_now(&mut data, datetime!(2026-10-17 12:00));
+        assert_eq!(
+            day_summary(&data, date!(2026 - 10 - 17)),
+            "No school today.\n"
+        );
+        assert_eq!(
+            day_summary(&data, date!(2026 - 10 - 18)),
+            "No school on Sun 2026-10-18.\n"
+        );
+    }
+}
@@ -1,95 +1,944 @@
 use std::{
-    io::{Write, stdout},
+    io::{IsTerminal, Write, stdout},
+    path::PathBuf,
     thread::sleep,
-    time::Duration,
+    time::{Duration, Instant, SystemTime},
 };
 
+use serde::Serialize;
 use time::PrimitiveDateTime;
 
+use crate::error::AppError;
+
+#[cfg(feature = "archive")]
+pub mod archive;
 pub mod data;
+pub mod error;
+pub mod export;
+pub mod model;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+#[cfg(feature = "notify")]
+pub mod notify;
+pub mod parse;
+pub mod ring;
 
 fn main() {
     env_logger::init();
     let opts = match parse_args(std::env::args().skip(1)) {
         Ok(opts) => opts,
+        Err(AppError::Help) => {
+            println!("{}", usage());
+            std::process::exit(0);
+        }
         Err(err) => {
-            if err == "Requested help." {
-                println!("{}", usage());
-                std::process::exit(0);
-            }
             eprintln!("{err}");
             eprintln!("{}", usage());
-            std::process::exit(2);
+            std::process::exit(err.exit_code());
         }
     };
     if let Err(err) = run(opts) {
         eprintln!("{err}");
-        std::process::exit(1);
+        std::process::exit(err.exit_code());
+    }
+}
+
+// --data wins over the school state file, which wins over the standard locations.
+fn load_data(
+    opts: &Options,
+    school: Option<&str>,
+) -> Result<(data::AppData, Option<PathBuf>), AppError> {
+    let data_dir = match (&opts.data_dir, school) {
+        (Some(dir), _) => Some(dir.clone()),
+        (None, Some(school)) => Some(school_dir(opts, school)?),
+        (None, None) => data::find_data_dir(),
+    };
+    let mut data = match &data_dir {
+        #[cfg(feature = "archive")]
+        Some(dir) if archive::is_archive(dir) => data::AppData::from_archive(dir)?,
+        #[cfg(not(feature = "archive"))]
+        Some(dir) if dir.is_file() => {
+            return Err(AppError::BadArgs(
+                "--data with an archive requires bell to be built with the `archive` feature"
+                    .to_string(),
+            ));
+        }
+        Some(dir) => data::AppData::from_schedule_dir(dir)?,
+        None => data::load_app_data(),
+    };
+    match &data_dir {
+        Some(dir) => log::info!("Loaded {} from {}", data.meta.name, dir.display()),
+        None => log::info!("Using the embedded schedule for {}", data.meta.name),
+    }
+    if let Some(tz) = &opts.tz {
+        data.meta.timezone = Some(tz.clone());
+    }
+    data.boundary = opts.boundary;
+    data.merge_adjacent = opts.merge_adjacent;
+    data.max_lookahead = opts.max_lookahead;
+    if let Some(at) = opts.at {
+        let now = data.now();
+        // A wall-clock target takes the offset of its own date, not today's.
+        data.clock_offset = match at {
+            AtArg::Offset(offset) => offset,
+            AtArg::Time(time) => data.local_datetime(now.date(), time) - now,
+            AtArg::DateTime(at) => data.local_datetime(at.date(), at.time()) - now,
+        };
+    }
+    if let Some(name) = &opts.override_schedule
+        && !data.schedules.schedules.contains_key(name)
+    {
+        return Err(AppError::BadArgs(format!(
+            "Unknown override schedule: {name}"
+        )));
+    }
+    data.override_schedule = active_override(opts, &data);
+    if let Some(tz) = &data.meta.timezone
+        && data::timezone_by_name(tz).is_none()
+    {
+        return Err(AppError::BadArgs(format!("Unknown timezone: {tz}")));
+    }
+    Ok((data, data_dir))
+}
+
+// An unknown school falls back to --default-school, if that one exists.
+fn school_dir(opts: &Options, school: &str) -> Result<PathBuf, AppError> {
+    if let Some(dir) = data::find_school_dir(school) {
+        return Ok(dir);
+    }
+    if let Some(fallback) = &opts.default_school
+        && let Some(dir) = data::find_school_dir(fallback)
+    {
+        log::warn!("Unknown school {school}, using {fallback}");
+        return Ok(dir);
+    }
+    let schools = data::available_schools();
+    if schools.is_empty() {
+        return Err(AppError::NoSchool(format!(
+            "Unknown school: {school} (no schools found in $XDG_CONFIG_HOME/bell or /etc/bell)"
+        )));
+    }
+    Err(AppError::NoSchool(format!(
+        "Unknown school: {school} (available: {})",
+        schools.join(", ")
+    )))
+}
+
+// --override wins; otherwise a non-empty override file names today's schedule, which
+// is checked on every tick so a drill can be started and ended while bell runs.
+fn active_override(opts: &Options, data: &data::AppData) -> Option<String> {
+    if let Some(name) = &opts.override_schedule {
+        return Some(name.clone());
+    }
+    let path = opts.override_file.as_deref()?;
+    let name = data::read_school_file(path)?;
+    if !data.schedules.schedules.contains_key(&name) {
+        log::warn!(
+            "Ignoring unknown override schedule {name} in {}",
+            path.display()
+        );
+        return None;
+    }
+    Some(name)
+}
+
+fn modified(path: &std::path::Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+// Newest modification time of the files a data directory is loaded from.
+fn data_stamp(dir: &std::path::Path) -> Option<SystemTime> {
+    if dir.is_file() {
+        return modified(dir);
     }
+    ["meta.json", "schedules.bell"]
+        .iter()
+        .chain(data::CALENDAR_LAYERS)
+        .filter_map(|name| modified(&dir.join(name)))
+        .max()
 }
 
-fn run(opts: Options) -> Result<(), String> {
-    let data = data::load_app_data();
+const RELOAD_ATTEMPTS: u32 = 5;
+
+// Reloads the data when the school file or the data directory changes. A failed
+// load (say, a file caught mid-write) keeps the previous data and is retried with
+// a doubling delay; after RELOAD_ATTEMPTS failures it waits for the next change.
+struct DataWatch {
+    school_file: Option<PathBuf>,
+    data_dir: Option<PathBuf>,
+    stamp: (Option<SystemTime>, Option<SystemTime>),
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+impl DataWatch {
+    fn new(school_file: Option<PathBuf>, data_dir: Option<PathBuf>) -> Self {
+        let mut watch = Self {
+            school_file,
+            data_dir,
+            stamp: (None, None),
+            failures: 0,
+            retry_at: None,
+        };
+        watch.stamp = watch.current_stamp();
+        watch
+    }
+
+    fn current_stamp(&self) -> (Option<SystemTime>, Option<SystemTime>) {
+        (
+            self.school_file.as_deref().and_then(modified),
+            self.data_dir.as_deref().and_then(data_stamp),
+        )
+    }
+
+    fn poll(&mut self, opts: &Options) -> Option<data::AppData> {
+        let stamp = self.current_stamp();
+        if stamp == self.stamp || self.retry_at.is_some_and(|at| Instant::now() < at) {
+            return None;
+        }
+        let school = self.school_file.as_deref().and_then(data::read_school_file);
+        match load_data(opts, school.as_deref()) {
+            Ok((data, data_dir)) => {
+                self.data_dir = data_dir;
+                self.stamp = self.current_stamp();
+                self.failures = 0;
+                self.retry_at = None;
+                Some(data)
+            }
+            Err(err) => {
+                self.failures += 1;
+                if self.failures >= RELOAD_ATTEMPTS {
+                    log::error!(
+                        "Reloading schedule data failed {} times, keeping the previous data: {err}",
+                        self.failures
+                    );
+                    self.stamp = stamp;
+                    self.failures = 0;
+                    self.retry_at = None;
+                } else {
+                    let delay = Duration::from_secs(1 << self.failures);
+                    log::warn!(
+                        "Reloading schedule data failed, retrying in {}s: {err}",
+                        delay.as_secs()
+                    );
+                    self.retry_at = Some(Instant::now() + delay);
+                }
+                None
+            }
+        }
+    }
+}
+
+fn run(opts: Options) -> Result<(), AppError> {
+    let school_file = match opts.data_dir {
+        Some(_) => None,
+        None => opts.school_file.clone().or_else(data::default_school_file),
+    };
+    let school = school_file.as_deref().and_then(data::read_school_file);
+    let (mut data, data_dir) = load_data(&opts, school.as_deref())?;
+    if opts.dry_run {
+        println!("{opts:#?}");
+        match &data_dir {
+            Some(dir) => println!("data: {} from {}", data.meta.name, dir.display()),
+            None => println!("data: embedded schedule for {}", data.meta.name),
+        }
+        return Ok(());
+    }
+    let mut watch = DataWatch::new(school_file, data_dir.clone());
+    let today = data.now().date();
+    match data.schedule_name_for_date(today) {
+        Some(name) if !data.schedules.schedules.contains_key(name) => {
+            log::warn!("Schedule '{name}' for {today} is not defined")
+        }
+        Some(name) => log::debug!("Schedule for {today}: {name}"),
+        None => log::debug!("No schedule for {today}"),
+    }
+    if opts.instructional_minutes {
+        let date = opts.date.map_or(today, |date| date.resolve(today));
+        println!("{}", data.instructional_minutes(date, &opts.exclude));
+        return Ok(());
+    }
+    if let Some((start, end)) = opts.overlaps {
+        let date = opts.date.map_or(today, |date| date.resolve(today));
+        let periods = data.periods_overlapping(date, start, end);
+        for (period, period_end) in &periods {
+            println!(
+                "{}-{} {}",
+                clock_time(period.start),
+                clock_time(*period_end),
+                period.msg
+            );
+        }
+        std::process::exit(if periods.is_empty() { 1 } else { 0 });
+    }
+    if opts.transitions {
+        let date = opts.date.map_or(today, |date| date.resolve(today));
+        print!("{}", export::day_transitions(&data, date));
+        return Ok(());
+    }
+    if opts.schedule_json {
+        let date = opts.date.map_or(today, |date| date.resolve(today));
+        println!("{}", export::day_json(&data, date));
+        return Ok(());
+    }
+    if let Some(pattern) = &opts.explain_format {
+        print!("{}", explain_pattern(pattern));
+        return Ok(());
+    }
+    if opts.peek {
+        println!("{}", peek_line(&data));
+        return Ok(());
+    }
+    if opts.summary {
+        let date = opts.date.map_or(today, |date| date.resolve(today));
+        print!("{}", export::day_summary(&data, date));
+        return Ok(());
+    }
+    if opts.in_session {
+        let now_dt = data.now();
+        std::process::exit(if data.in_session(now_dt.date(), now_dt.time()) {
+            0
+        } else {
+            1
+        });
+    }
+    if let Some(export) = opts.export {
+        let week_of = opts.week.map_or(today, |week| week.resolve(today));
+        match export {
+            ExportFormat::Table => print!("{}", export::weekly_table(&data, week_of)),
+            ExportFormat::Dot => print!("{}", export::weekly_dot(&data, week_of)),
+        }
+        return Ok(());
+    }
+    // Any of the modes above wins over the plain --date listing.
+    if let Some(date) = opts.date {
+        let color = match opts.color {
+            ColorMode::Auto => stdout().is_terminal(),
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+        };
+        print!(
+            "{}",
+            export::day_listing(&data, date.resolve(today), color, opts.around)
+        );
+        return Ok(());
+    }
+    if let Some(index) = opts.until_index {
+        let count = data
+            .visible_periods(today)
+            .map_or(0, |periods| periods.len());
+        if index > count {
+            return Err(AppError::BadArgs(format!(
+                "--until-index {index} is out of range, today's schedule has {count} periods"
+            )));
+        }
+    }
+    let mut outputs = vec![(opts.format.clone(), Sink::Stdout)];
+    if let Some(path) = &opts.output_file {
+        outputs.push((opts.output_format.clone(), Sink::File(path.clone())));
+    }
+    if opts.sleep_hint {
+        let snap = snapshot(&data, &opts)
+            .ok_or_else(|| AppError::Other("No current or upcoming periods found.".to_string()))?;
+        // Only patterns follow --round; the built-in formats always round down.
+        let round = match opts.format {
+            OutputFormat::Pattern(_) => opts.round,
+            _ => Rounding::Down,
+        };
+        println!(
+            "{}",
+            next_change_in(opts.format.time_tokens(), snap.remaining, round)
+        );
+        return Ok(());
+    }
     if opts.once {
-        if let Some((label, msg, remaining)) = current_or_next(&data) {
-            let line = match &opts.format {
-                OutputFormat::Plain => default_line(label, &msg, remaining),
-                OutputFormat::Pattern(pattern) => {
-                    format_line_with_pattern(pattern, label, &msg, remaining)
+        if let Some(mut snap) = snapshot(&data, &opts) {
+            snap.remaining = round_subsecond(snap.remaining, opts.snapshot_round);
+            for (format, sink) in &outputs {
+                if let OutputFormat::Binary = format {
+                    sink.write_frame(&binary_frame(&opts, &data, &snap));
+                    continue;
                 }
-            };
-            print_line(line, true);
+                let line = render_line(&opts, &data, format, &snap, "\n");
+                // For embedding the line in other text; a file still ends with a newline.
+                if opts.no_newline && matches!(sink, Sink::Stdout) {
+                    let mut out = stdout().lock();
+                    out.write_all(line.as_bytes()).unwrap();
+                    out.flush().unwrap();
+                    continue;
+                }
+                sink.write(line, true);
+            }
+            // i3blocks marks the block urgent when the command exits with 33.
+            if matches!(opts.format, OutputFormat::I3blocks)
+                && urgency_for(&opts, snap.remaining) == Urgency::Alert
+            {
+                std::process::exit(33);
+            }
         } else {
-            return Err("No current or upcoming periods found.".to_string());
+            return Err(AppError::Other(
+                "No current or upcoming periods found.".to_string(),
+            ));
         }
         return Ok(());
     }
+    // The event log takes over stdout; an --output-file still gets the live line.
+    if opts.follow {
+        outputs.retain(|(_, sink)| !matches!(sink, Sink::Stdout));
+    }
+    #[cfg(feature = "mqtt")]
+    let mut mqtt = match &opts.mqtt_broker {
+        // The connection itself runs in the background, so only a bad broker URL fails here.
+        Some(broker) => Some(
+            mqtt::MqttPublisher::connect(broker, &opts.mqtt_topic).map_err(AppError::BadArgs)?,
+        ),
+        None => None,
+    };
+    #[cfg(not(feature = "mqtt"))]
+    if opts.mqtt_broker.is_some() {
+        return Err(AppError::BadArgs(
+            "--mqtt requires bell to be built with the `mqtt` feature".to_string(),
+        ));
+    }
+    // The live line has no trailing newline, so Ctrl-C would leave the prompt behind it.
+    let overwrites = outputs.iter().any(|(format, sink)| {
+        matches!(sink, Sink::Stdout) && !matches!(format, OutputFormat::Binary)
+    });
+    if overwrites
+        && stdout().is_terminal()
+        && let Err(err) = ctrlc::set_handler(|| {
+            let mut out = stdout().lock();
+            let _ = out.write_all(b"\x1b[K\n");
+            let _ = out.flush();
+            std::process::exit(130);
+        })
+    {
+        log::warn!("Failed to install the Ctrl-C handler: {err}");
+    }
+    let mut last_period: Option<(&'static str, String)> = None;
+    #[cfg(feature = "notify")]
+    let mut day_over_notified = {
+        let now_dt = data.now();
+        data.is_day_over(now_dt.date(), now_dt.time())
+            .then(|| now_dt.date())
+    };
+    let current_bell = |data: &data::AppData, now_dt: time::OffsetDateTime| {
+        data.current_section(now_dt.date(), now_dt.time())
+            .map(|section| (now_dt.date(), section.current_period))
+    };
+    let mut last_bell = current_bell(&data, data.now()).map(|(date, period)| (date, period.start));
+    let mut last_tick = data.now();
+    let mut updates = 0;
+    let mut override_raw = opts
+        .override_file
+        .as_deref()
+        .and_then(data::read_school_file);
+    let mut next_sleep = Duration::from_secs(opts.interval_secs);
     loop {
-        sleep(Duration::from_secs(opts.interval_secs));
-        if let Some((label, msg, remaining)) = current_or_next(&data) {
-            let line = match &opts.format {
-                OutputFormat::Plain => default_line(label, &msg, remaining),
-                OutputFormat::Pattern(pattern) => {
-                    format_line_with_pattern(pattern, label, &msg, remaining)
+        sleep(align_to_second(next_sleep));
+        next_sleep = Duration::from_secs(opts.interval_secs);
+        if let Some(new_data) = watch.poll(&opts) {
+            data = new_data;
+            last_period = None;
+            log::info!("Reloaded schedule data for {}", data.meta.name);
+        }
+        if let Some(path) = &opts.override_file {
+            let raw = data::read_school_file(path);
+            if raw != override_raw {
+                override_raw = raw;
+                data.override_schedule = active_override(&opts, &data);
+                last_period = None;
+                match &data.override_schedule {
+                    Some(name) => log::info!("Override schedule {name} is active"),
+                    None => log::info!("Override schedule cleared"),
+                }
+            }
+        }
+        let now_dt = data.now();
+        if now_dt < last_tick {
+            log::warn!("System clock went backwards from {last_tick} to {now_dt}");
+            // Forget the previous period so the jump neither notifies nor leaves a stale line.
+            last_period = None;
+            if overwrites && stdout().is_terminal() {
+                print!("\r\x1b[2K");
+            }
+        }
+        last_tick = now_dt;
+        let bell = current_bell(&data, now_dt);
+        let bell_key = bell.as_ref().map(|(date, period)| (*date, period.start));
+        if bell_key != last_bell {
+            last_bell = bell_key;
+            if let (Some(command), Some((_, period))) = (&opts.ring_command, &bell)
+                && period.ring
+            {
+                ring::ring(command, period);
+            }
+        }
+        if let Some(snap) = snapshot(&data, &opts) {
+            let (label, msg, remaining) = (snap.label, snap.msg.clone(), snap.remaining);
+            #[cfg(feature = "notify")]
+            if let Some(notify_opts) = &opts.notify {
+                let today = now_dt.date();
+                // Only period starts and ends count; "Next" turning into "Tomorrow" does not.
+                let changed = last_period.as_ref().is_some_and(|(last_label, last_msg)| {
+                    (*last_label != label || *last_msg != msg)
+                        && (label == "Current" || *last_label == "Current")
+                });
+                // Quiet hours only hold back the notification, not the bookkeeping.
+                let quiet = notify_opts.is_quiet(now_dt.time());
+                if data.is_day_over(today, now_dt.time()) && day_over_notified != Some(today) {
+                    day_over_notified = Some(today);
+                    if !quiet {
+                        notify::send(notify_opts, &data.meta.name, &notify_opts.day_over_text);
+                    }
+                } else if changed && !quiet {
+                    notify::send(notify_opts, &data.meta.name, &format!("{label}: {msg}"));
                 }
+            }
+            #[cfg(feature = "mqtt")]
+            if let Some(publisher) = mqtt.as_mut() {
+                publisher.publish_if_changed(label, &msg, remaining);
+            }
+            if last_period
+                .as_ref()
+                .is_none_or(|(last_label, last_msg)| *last_label != label || *last_msg != msg)
+            {
+                log::info!("{label}: {msg}");
+                if opts.follow {
+                    let stamp = now_dt
+                        .format(time::macros::format_description!(
+                            "[year]-[month]-[day]T[hour]:[minute]:[second]"
+                        ))
+                        .unwrap();
+                    match label {
+                        "Current" => println!("{stamp} -> {msg}"),
+                        _ => println!("{stamp} -> {label}: {msg}"),
+                    }
+                }
+            }
+            last_period = Some((label, msg.clone()));
+            for (format, sink) in &outputs {
+                if let OutputFormat::Binary = format {
+                    sink.write_frame(&binary_frame(&opts, &data, &snap));
+                    continue;
+                }
+                let line = render_line(&opts, &data, format, &snap, " | ");
+                sink.write(line, false);
+            }
+            updates += 1;
+            if opts.count.is_some_and(|count| updates >= count) {
+                if overwrites {
+                    println!();
+                }
+                return Ok(());
+            }
+            next_sleep = match opts.sync {
+                SyncUnit::Second => tick_interval(&opts, remaining),
+                SyncUnit::Minute => until_next_minute(),
             };
-            print_line(line, false);
         }
     }
 }
 
+// Shortens the sleep by the current sub-second offset so ticks land just after a
+// whole wall-clock second instead of drifting.
+fn align_to_second(duration: Duration) -> Duration {
+    let subsec = Duration::from_nanos(u64::from(time::OffsetDateTime::now_utc().nanosecond()));
+    duration.saturating_sub(subsec)
+}
+
+// Whole seconds left in the current minute; `align_to_second` trims the fraction.
+fn until_next_minute() -> Duration {
+    let second = time::OffsetDateTime::now_utc().second();
+    Duration::from_secs(60 - u64::from(second))
+}
+
+// Ticks every second during the final countdown and at `interval_secs` otherwise,
+// waking up early enough not to skip past the start of the countdown.
+fn tick_interval(opts: &Options, remaining: time::Duration) -> Duration {
+    let interval = Duration::from_secs(opts.interval_secs);
+    let fast = Duration::from_secs(1).min(interval);
+    if remaining < opts.alert_below {
+        return fast;
+    }
+    let until_alert = Duration::try_from(remaining - opts.alert_below).unwrap_or(Duration::ZERO);
+    interval.min(until_alert.max(fast))
+}
+
 #[derive(Debug, Clone)]
 enum OutputFormat {
     Plain,
+    NowNext,
+    Tmux,
+    I3blocks,
+    Json,
+    Binary,
     Pattern(String),
 }
 
+impl OutputFormat {
+    // The tokens that stand in for how the built-in formats show the remaining time.
+    fn time_tokens(&self) -> &str {
+        match self {
+            OutputFormat::Plain | OutputFormat::Json => "[SS]",
+            OutputFormat::NowNext
+            | OutputFormat::Tmux
+            | OutputFormat::I3blocks
+            | OutputFormat::Binary => "[m]",
+            OutputFormat::Pattern(pattern) => pattern,
+        }
+    }
+}
+
+fn parse_format(value: String) -> OutputFormat {
+    match value.as_str() {
+        "plain" => OutputFormat::Plain,
+        "now-next" => OutputFormat::NowNext,
+        "tmux" => OutputFormat::Tmux,
+        "i3blocks" => OutputFormat::I3blocks,
+        "json" => OutputFormat::Json,
+        "binary" => OutputFormat::Binary,
+        "short" => OutputFormat::Pattern("[Period] [MM]m".to_string()),
+        _ => OutputFormat::Pattern(value),
+    }
+}
+
+enum Sink {
+    Stdout,
+    File(PathBuf),
+}
+
+impl Sink {
+    // A file sink always holds just the latest line, so readers never see a partial history.
+    fn write(&self, line: String, newline: bool) {
+        match self {
+            Sink::Stdout => print_line(line, newline),
+            Sink::File(path) => {
+                if let Err(err) = std::fs::write(path, format!("{line}\n")) {
+                    log::warn!("Failed to write {}: {err}", path.display());
+                }
+            }
+        }
+    }
+
+    // Binary frames go out as they are, without a newline or carriage return.
+    fn write_frame(&self, frame: &[u8]) {
+        match self {
+            Sink::Stdout => {
+                let mut out = stdout().lock();
+                out.write_all(frame).unwrap();
+                out.flush().unwrap();
+            }
+            Sink::File(path) => {
+                if let Err(err) = std::fs::write(path, frame) {
+                    log::warn!("Failed to write {}: {err}", path.display());
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum DateArg {
+    On(time::Date),
+    Today,
+    Tomorrow,
+    Next(time::Weekday),
+}
+
+impl DateArg {
+    // Weekday names resolve to the next occurrence, which is today if it matches.
+    fn resolve(self, today: time::Date) -> time::Date {
+        match self {
+            DateArg::On(date) => date,
+            DateArg::Today => today,
+            DateArg::Tomorrow => today.next_day().unwrap_or(today),
+            DateArg::Next(weekday) => {
+                let mut date = today;
+                while date.weekday() != weekday {
+                    date = date.next_day().unwrap_or(date);
+                }
+                date
+            }
+        }
+    }
+}
+
+// --at moves the clock: "+90m"/"-2h" from now, "14:05" today or "2026-09-02T08:50".
+#[derive(Debug, Clone, Copy)]
+enum AtArg {
+    Offset(time::Duration),
+    Time(time::Time),
+    DateTime(PrimitiveDateTime),
+}
+
+fn parse_at(value: &str) -> Result<AtArg, String> {
+    let invalid = |_| format!("Invalid value for --at: {value}");
+    if let Some(rest) = value.strip_prefix('+') {
+        let secs = parse::parse_seconds(rest).map_err(invalid)?;
+        return Ok(AtArg::Offset(time::Duration::seconds(i64::from(secs))));
+    }
+    if let Some(rest) = value.strip_prefix('-') {
+        let secs = parse::parse_seconds(rest).map_err(invalid)?;
+        return Ok(AtArg::Offset(-time::Duration::seconds(i64::from(secs))));
+    }
+    if value.contains('T') {
+        let format = time::macros::format_description!("[year]-[month]-[day]T[hour]:[minute]");
+        return PrimitiveDateTime::parse(value, format)
+            .map(AtArg::DateTime)
+            .map_err(|_| format!("Invalid value for --at: {value}"));
+    }
+    parse::parse_start_time(value)
+        .map(AtArg::Time)
+        .map_err(invalid)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ExportFormat {
+    Table,
+    Dot,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Urgency {
+    Normal,
+    Warning,
+    Alert,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GapBehavior {
+    Hold,
+    Advance,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SyncUnit {
+    Second,
+    Minute,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CountFrom {
+    Start,
+    End,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ColorMode {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Rounding {
+    Down,
+    Up,
+    Nearest,
+}
+
 #[derive(Debug, Clone)]
 struct Options {
     format: OutputFormat,
     once: bool,
+    dry_run: bool,
     interval_secs: u64,
+    #[cfg(feature = "notify")]
+    notify: Option<notify::NotifyOptions>,
+    ring_command: Option<String>,
+    transition_grace: time::Duration,
+    round: Rounding,
+    warn_below: time::Duration,
+    alert_below: time::Duration,
+    color_thresholds: Vec<(time::Duration, String)>,
+    mqtt_broker: Option<String>,
+    mqtt_topic: String,
+    ends_in_text: String,
+    starts_in_text: String,
+    separator: String,
+    label_separator: String,
+    tz: Option<String>,
+    boundary: data::Boundary,
+    export: Option<ExportFormat>,
+    week: Option<DateArg>,
+    date: Option<DateArg>,
+    no_label: bool,
+    no_newline: bool,
+    output_file: Option<PathBuf>,
+    output_format: OutputFormat,
+    color: ColorMode,
+    count_from: CountFrom,
+    data_dir: Option<PathBuf>,
+    sync: SyncUnit,
+    school_file: Option<PathBuf>,
+    default_school: Option<String>,
+    summary: bool,
+    peek: bool,
+    in_session: bool,
+    until_index: Option<usize>,
+    snapshot_round: Rounding,
+    explain_format: Option<String>,
+    schedule_json: bool,
+    follow: bool,
+    sleep_hint: bool,
+    count: Option<u64>,
+    max_lookahead: Option<u32>,
+    duration_width: usize,
+    adaptive_below: Option<time::Duration>,
+    at: Option<AtArg>,
+    rotate_every_secs: u64,
+    around: Option<usize>,
+    transitions: bool,
+    overlaps: Option<(time::Time, time::Time)>,
+    instructional_minutes: bool,
+    exclude: Vec<String>,
+    gap: GapBehavior,
+    only_during_school: bool,
+    merge_adjacent: bool,
+    override_schedule: Option<String>,
+    override_file: Option<PathBuf>,
+}
+
+fn parse_args<I>(args: I) -> Result<Options, AppError>
+where
+    I: Iterator<Item = String>,
+{
+    match parse_options(args) {
+        Ok(Some(opts)) => Ok(opts),
+        Ok(None) => Err(AppError::Help),
+        Err(err) => Err(AppError::BadArgs(err)),
+    }
 }
 
-fn parse_args<I>(mut args: I) -> Result<Options, String>
+// None when --help was given.
+fn parse_options<I>(mut args: I) -> Result<Option<Options>, String>
 where
     I: Iterator<Item = String>,
 {
     let mut opts = Options {
         format: OutputFormat::Plain,
         once: false,
+        dry_run: false,
         interval_secs: 1,
+        #[cfg(feature = "notify")]
+        notify: None,
+        ring_command: None,
+        transition_grace: time::Duration::ZERO,
+        round: Rounding::Down,
+        warn_below: time::Duration::minutes(5),
+        alert_below: time::Duration::minutes(1),
+        color_thresholds: Vec::new(),
+        mqtt_broker: None,
+        mqtt_topic: "bell/current".to_string(),
+        ends_in_text: "Ends in".to_string(),
+        starts_in_text: "Starts in".to_string(),
+        separator: " | ".to_string(),
+        label_separator: ": ".to_string(),
+        tz: None,
+        boundary: data::Boundary::Inclusive,
+        export: None,
+        week: None,
+        date: None,
+        no_label: false,
+        no_newline: false,
+        output_file: None,
+        output_format: OutputFormat::Json,
+        color: ColorMode::Auto,
+        count_from: CountFrom::End,
+        data_dir: None,
+        sync: SyncUnit::Second,
+        school_file: None,
+        default_school: None,
+        summary: false,
+        peek: false,
+        in_session: false,
+        until_index: None,
+        snapshot_round: Rounding::Down,
+        explain_format: None,
+        schedule_json: false,
+        follow: false,
+        sleep_hint: false,
+        count: None,
+        max_lookahead: None,
+        duration_width: 0,
+        adaptive_below: None,
+        at: None,
+        rotate_every_secs: 10,
+        around: None,
+        transitions: false,
+        overlaps: None,
+        instructional_minutes: false,
+        exclude: Vec::new(),
+        gap: GapBehavior::Hold,
+        only_during_school: false,
+        merge_adjacent: false,
+        override_schedule: None,
+        override_file: None,
     };
     while let Some(arg) = args.next() {
         match arg.as_str() {
             "--once" => opts.once = true,
+            "--dry-run" => opts.dry_run = true,
+            "--no-label" => opts.no_label = true,
+            "--no-newline" => opts.no_newline = true,
             "--format" => {
                 let value = args.next().ok_or_else(|| "Missing value for --format".to_string())?;
-                if value == "plain" {
-                    opts.format = OutputFormat::Plain;
-                } else {
-                    opts.format = OutputFormat::Pattern(value);
-                }
+                opts.format = parse_format(value);
+            }
+            "--output-file" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --output-file".to_string())?;
+                opts.output_file = Some(PathBuf::from(value));
+            }
+            "--output-format" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --output-format".to_string())?;
+                opts.output_format = parse_format(value);
+            }
+            "--adaptive-precision" => {
+                opts.adaptive_below = Some(seconds_arg(&mut args, "--adaptive-precision")?);
+            }
+            "--duration-width" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --duration-width".to_string())?;
+                opts.duration_width = value
+                    .parse()
+                    .map_err(|_| "Invalid value for --duration-width".to_string())?;
+            }
+            "--count" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --count".to_string())?;
+                opts.count = Some(
+                    value
+                        .parse()
+                        .map_err(|_| "Invalid value for --count".to_string())?,
+                );
+            }
+            "--max-lookahead" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --max-lookahead".to_string())?;
+                let days: u32 = value
+                    .parse()
+                    .ok()
+                    .filter(|days| *days > 0)
+                    .ok_or_else(|| format!("Invalid value for --max-lookahead: {value}"))?;
+                opts.max_lookahead = Some(days);
+            }
+            "--rotate-every" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --rotate-every".to_string())?;
+                opts.rotate_every_secs = value
+                    .parse()
+                    .map_err(|_| "Invalid value for --rotate-every".to_string())?;
             }
             "--interval" => {
                 let value =
@@ -98,27 +947,835 @@ where
                     .parse()
                     .map_err(|_| "Invalid value for --interval".to_string())?;
             }
-            "--help" | "-h" => return Err("Requested help.".to_string()),
+            "--gap-behavior" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --gap-behavior".to_string())?;
+                opts.gap = match value.as_str() {
+                    "hold" => GapBehavior::Hold,
+                    "advance" => GapBehavior::Advance,
+                    _ => return Err(format!("Invalid value for --gap-behavior: {value}")),
+                };
+            }
+            "--transition-grace" => {
+                opts.transition_grace = seconds_arg(&mut args, "--transition-grace")?
+            }
+            "--separator" => {
+                opts.separator = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --separator".to_string())?;
+            }
+            "--label-separator" => {
+                opts.label_separator = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --label-separator".to_string())?;
+            }
+            "--ends-in-text" => {
+                opts.ends_in_text = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --ends-in-text".to_string())?;
+            }
+            "--starts-in-text" => {
+                opts.starts_in_text = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --starts-in-text".to_string())?;
+            }
+            "--explain-format" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --explain-format".to_string())?;
+                opts.explain_format = Some(value);
+            }
+            "--snapshot-round" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --snapshot-round".to_string())?;
+                opts.snapshot_round = match value.as_str() {
+                    "down" => Rounding::Down,
+                    "up" => Rounding::Up,
+                    "nearest" => Rounding::Nearest,
+                    _ => return Err(format!("Invalid value for --snapshot-round: {value}")),
+                };
+            }
+            "--round" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --round".to_string())?;
+                opts.round = match value.as_str() {
+                    "down" => Rounding::Down,
+                    "up" => Rounding::Up,
+                    "nearest" => Rounding::Nearest,
+                    _ => return Err(format!("Invalid value for --round: {value}")),
+                };
+            }
+            "--warn-below" => opts.warn_below = seconds_arg(&mut args, "--warn-below")?,
+            "--alert-below" => opts.alert_below = seconds_arg(&mut args, "--alert-below")?,
+            "--color-thresholds" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --color-thresholds".to_string())?;
+                opts.color_thresholds = parse_color_thresholds(&value)?;
+            }
+            "--ring-command" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --ring-command".to_string())?;
+                opts.ring_command = Some(value);
+            }
+            #[cfg(feature = "notify")]
+            "--notify" => {
+                opts.notify
+                    .get_or_insert_with(notify::NotifyOptions::default);
+            }
+            #[cfg(feature = "notify")]
+            "--notify-urgency" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --notify-urgency".to_string())?;
+                opts.notify
+                    .get_or_insert_with(notify::NotifyOptions::default)
+                    .urgency = notify::parse_urgency(&value)?;
+            }
+            #[cfg(feature = "notify")]
+            "--notify-timeout" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --notify-timeout".to_string())?;
+                opts.notify
+                    .get_or_insert_with(notify::NotifyOptions::default)
+                    .timeout = notify::parse_timeout(&value)?;
+            }
+            #[cfg(feature = "notify")]
+            "--notify-day-over" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --notify-day-over".to_string())?;
+                opts.notify
+                    .get_or_insert_with(notify::NotifyOptions::default)
+                    .day_over_text = value;
+            }
+            #[cfg(feature = "notify")]
+            "--quiet-hours" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --quiet-hours".to_string())?;
+                opts.notify
+                    .get_or_insert_with(notify::NotifyOptions::default)
+                    .quiet_hours = Some(notify::parse_quiet_hours(&value)?);
+            }
+            #[cfg(not(feature = "notify"))]
+            "--notify" | "--notify-urgency" | "--notify-timeout" | "--notify-day-over"
+            | "--quiet-hours" => {
+                return Err(format!(
+                    "{arg} requires bell to be built with the `notify` feature"
+                ));
+            }
+            "--mqtt" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --mqtt".to_string())?;
+                opts.mqtt_broker = Some(value);
+            }
+            "--mqtt-topic" => {
+                opts.mqtt_topic = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --mqtt-topic".to_string())?;
+            }
+            "--boundary" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --boundary".to_string())?;
+                opts.boundary = match value.as_str() {
+                    "inclusive" => data::Boundary::Inclusive,
+                    "exclusive" => data::Boundary::Exclusive,
+                    _ => return Err(format!("Invalid value for --boundary: {value}")),
+                };
+            }
+            "--export" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --export".to_string())?;
+                opts.export = match value.as_str() {
+                    "table" => Some(ExportFormat::Table),
+                    "dot" => Some(ExportFormat::Dot),
+                    _ => return Err(format!("Invalid value for --export: {value}")),
+                };
+            }
+            "--week" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --week".to_string())?;
+                opts.week = Some(parse_date(&value)?);
+            }
+            "--list" => opts.date = Some(DateArg::Today),
+            "--summary" => opts.summary = true,
+            "--peek" => opts.peek = true,
+            "--follow" => opts.follow = true,
+            "--sleep-hint" => opts.sleep_hint = true,
+            "--around" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --around".to_string())?;
+                opts.around = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("Invalid value for --around: {value}"))?,
+                );
+            }
+            "--in-session" => opts.in_session = true,
+            "--until-index" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --until-index".to_string())?;
+                let index: usize = value
+                    .parse()
+                    .map_err(|_| format!("Invalid value for --until-index: {value}"))?;
+                if index == 0 {
+                    return Err("--until-index counts periods from 1".to_string());
+                }
+                opts.until_index = Some(index);
+            }
+            "--only-during-school" => opts.only_during_school = true,
+            "--merge-adjacent" => opts.merge_adjacent = true,
+            "--override" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --override".to_string())?;
+                opts.override_schedule = Some(value);
+            }
+            "--override-file" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --override-file".to_string())?;
+                opts.override_file = Some(PathBuf::from(value));
+            }
+            "--schedule-json" => opts.schedule_json = true,
+            "--transitions" => opts.transitions = true,
+            "--overlaps" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --overlaps".to_string())?;
+                let invalid = || format!("Invalid value for --overlaps: {value}");
+                let (start, end) = value.split_once('-').ok_or_else(invalid)?;
+                let start = parse::parse_start_time(start).map_err(|_| invalid())?;
+                let end = parse::parse_start_time(end).map_err(|_| invalid())?;
+                if end <= start {
+                    return Err(invalid());
+                }
+                opts.overlaps = Some((start, end));
+            }
+            "--instructional-minutes" => opts.instructional_minutes = true,
+            "--exclude" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --exclude".to_string())?;
+                opts.exclude = value
+                    .split(',')
+                    .map(|keyword| keyword.trim().to_string())
+                    .filter(|keyword| !keyword.is_empty())
+                    .collect();
+            }
+            "--color" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --color".to_string())?;
+                opts.color = match value.as_str() {
+                    "auto" => ColorMode::Auto,
+                    "always" => ColorMode::Always,
+                    "never" => ColorMode::Never,
+                    _ => return Err(format!("Invalid value for --color: {value}")),
+                };
+            }
+            "--sync" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --sync".to_string())?;
+                opts.sync = match value.as_str() {
+                    "second" => SyncUnit::Second,
+                    "minute" => SyncUnit::Minute,
+                    _ => return Err(format!("Invalid value for --sync: {value}")),
+                };
+            }
+            "--default-school" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --default-school".to_string())?;
+                opts.default_school = Some(value);
+            }
+            "--school-file" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --school-file".to_string())?;
+                opts.school_file = Some(PathBuf::from(value));
+            }
+            "--data" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --data".to_string())?;
+                opts.data_dir = Some(PathBuf::from(value));
+            }
+            "--count-from" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --count-from".to_string())?;
+                opts.count_from = match value.as_str() {
+                    "start" => CountFrom::Start,
+                    "end" => CountFrom::End,
+                    _ => return Err(format!("Invalid value for --count-from: {value}")),
+                };
+            }
+            "--at" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --at".to_string())?;
+                opts.at = Some(parse_at(&value)?);
+            }
+            "--date" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --date".to_string())?;
+                opts.date = Some(parse_date(&value)?);
+            }
+            "--tz" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "Missing value for --tz".to_string())?;
+                opts.tz = Some(value);
+            }
+            "--help" | "-h" => return Ok(None),
             _ => return Err(format!("Unknown argument: {arg}")),
         }
     }
-    Ok(opts)
+    // Only the plain line and the [Adaptive] token know about the threshold.
+    if opts.adaptive_below.is_some() {
+        match &opts.format {
+            OutputFormat::Plain => {}
+            OutputFormat::Pattern(pattern) if pattern.contains("[Adaptive]") => {}
+            _ => {
+                return Err(
+                    "--adaptive-precision needs the plain format or an [Adaptive] token in --format"
+                        .to_string(),
+                );
+            }
+        }
+    }
+    Ok(Some(opts))
+}
+
+fn parse_date(value: &str) -> Result<DateArg, String> {
+    let weekday = match value.to_lowercase().as_str() {
+        "today" => return Ok(DateArg::Today),
+        "tomorrow" => return Ok(DateArg::Tomorrow),
+        "monday" | "mon" => time::Weekday::Monday,
+        "tuesday" | "tue" => time::Weekday::Tuesday,
+        "wednesday" | "wed" => time::Weekday::Wednesday,
+        "thursday" | "thu" => time::Weekday::Thursday,
+        "friday" | "fri" => time::Weekday::Friday,
+        "saturday" | "sat" => time::Weekday::Saturday,
+        "sunday" | "sun" => time::Weekday::Sunday,
+        _ => {
+            let format = time::macros::format_description!("[year]-[month]-[day]");
+            return time::Date::parse(value, format)
+                .map(DateArg::On)
+                .map_err(|_| {
+                    format!(
+                        "Invalid date: {value} (expected YYYY-MM-DD, today, tomorrow or a weekday)"
+                    )
+                });
+        }
+    };
+    Ok(DateArg::Next(weekday))
+}
+
+fn seconds_arg<I>(args: &mut I, flag: &str) -> Result<time::Duration, String>
+where
+    I: Iterator<Item = String>,
+{
+    let value = args
+        .next()
+        .ok_or_else(|| format!("Missing value for {flag}"))?;
+    let secs: u64 = value
+        .parse()
+        .map_err(|_| format!("Invalid value for {flag}"))?;
+    Ok(time::Duration::seconds(secs as i64))
+}
+
+// "600=yellow,60=red": each color applies below its number of seconds left.
+fn parse_color_thresholds(value: &str) -> Result<Vec<(time::Duration, String)>, String> {
+    let invalid = || format!("Invalid value for --color-thresholds: {value}");
+    let mut thresholds = Vec::new();
+    for pair in value.split(',') {
+        let (secs, color) = pair.split_once('=').ok_or_else(invalid)?;
+        let secs: u64 = secs.trim().parse().map_err(|_| invalid())?;
+        let color = color.trim();
+        if color.is_empty() {
+            return Err(invalid());
+        }
+        thresholds.push((time::Duration::seconds(secs as i64), color.to_string()));
+    }
+    thresholds.sort_by_key(|(below, _)| *below);
+    Ok(thresholds)
 }
 
 fn usage() -> &'static str {
-    "Usage: bell [--once] [--format plain|<pattern>] [--interval <secs>]
+    "Usage: bell [--once] [--format plain|<pattern>] [--interval <secs>] [--notify]
     --once                Print once and exit
+    --no-newline          With --once, leave out the newline after the printed line
+    --dry-run             Print the resolved options and exit
     --format plain        Default output format (with label/message)
-    --format <pattern>    Line pattern with tokens: [Label] [Period] [HH] [MM] [SS]
+    --no-label            Leave out the \"Current:\"/\"Next:\" prefix of the plain format
+    --format now-next     Current period and the one after it, on two lines
+                          (joined with \" | \" in continuous mode)
+    --format tmux         Compact tmux status-line segment colored by urgency
+    --format i3blocks     Full text, short text and color lines for i3blocks;
+                          with --once, exits 33 below the alert threshold
+    --format json         One JSON object with label, period and remaining_secs
+    --format short        Period and whole minutes left, e.g. \"Biology 23m\"
+    --format binary       Fixed 35-byte frame per update for serial displays: byte 0 is
+                          the state (0 before school, 1 passing, 2 in class, 3 after
+                          school), bytes 1-2 the whole minutes left (big-endian u16),
+                          bytes 3-34 the period name in UTF-8, zero-padded
+    --format <pattern>    Line pattern with tokens: [Label] [State] [Period] [Icon]
+                          [Week] [Comment] [NextBell] [Zone] [MetaName] [Schedule]
+                          [HH] [MM] [SS] [m] [s] [TotalSeconds] [ISO] [Elapsed]
+                          [Percent] [DayPercent] [NextChangeIn] [Adaptive]
+                          [ISO] is the time left as an ISO 8601 duration, e.g. PT1H5M9S
+                          [NextChangeIn] is the number of seconds until the line changes
+                          [MM]/[SS] are zero-padded, [m]/[s] are not: \"[m]m[s]s\" -> 5m3s
+                          [Adaptive] is \"23m\", or \"42s\" below --adaptive-precision
+                          (default: 60)
+                          [State] is before-school, passing, in-class or after-school;
+                          passing covers the meta.json \"breaks\" (e.g. Lunch) and the
+                          --transition-grace window
+                          [Comment] is today's calendar note, e.g. \"Spirit Week\"
+                          [NextBell] is the clock time of the next bell, e.g. 08:50
+                          [MetaName] is meta.json \"name\", [Schedule] today's schedule key
+                          [Zone] is the bell zone of today's schedule (\"zone <name>\")
+                          Before the first bell, [Elapsed]/[Percent] count from
+                          meta.json \"day_start\" (default: the first period start)
+                          [DayPercent] runs from 0 at the first bell to 100 at the end
+                          of the day (the last period's end, else the last bell)
                           Example: \"Period: [Period] | [HH]:[MM]:[SS]\"
-    --interval <secs>     Refresh interval for continuous mode (default: 1)"
+    --explain-format <pattern>
+                          List the tokens a pattern uses and flag unknown ones
+    --export table        Print a weekly schedule grid and exit
+    --export dot          Print the week's school days as a GraphViz digraph and exit
+    --week <date>         Week shown by --export (default: this week)
+    --date <date>         Print the schedule of one day and exit
+                          Dates are YYYY-MM-DD, today, tomorrow or a weekday name
+    --schedule-json       Print the periods of one day (--date, default: today) as a
+                          JSON array with start, end and msg, then exit
+    --transitions         Print every bell of one day (--date, default: today) as an
+                          RFC 3339 timestamp followed by end_of_<period> and/or
+                          start_of_<period> (spaces in names become _), then exit
+    --overlaps <from>-<to>
+                          Print the periods of one day (--date, default: today) that
+                          overlap a span like 10:00-11:00, then exit 0, or 1 if none do
+    --instructional-minutes
+                          Print the total minutes of one day's periods (--date, default:
+                          today), each lasting until the next bell, then exit
+    --exclude <keywords>  Comma-separated name keywords left out of
+                          --instructional-minutes, e.g. \"lunch,passing\"
+    --list                Print today's schedule and exit (same as --date today)
+    --around <n>          With --list, only show the current period and n periods
+                          before and after it
+    --peek                Print the time of the next bell and what it starts, then exit
+    --in-session          Exit 0 while school is in session and 1 otherwise, e.g.
+                          `bell --in-session && lights on`. The session runs from the
+                          first bell up to, not including, the last one; passing
+                          periods between classes count as in session
+    --until-index <n>     Count down to the start of the n-th period of today's
+                          schedule (1-based) instead of the next bell
+    --summary             Print the schedule name, first bell and end of day of one day
+                          (--date, default: today) and exit
+    --color auto|always|never
+                          Highlight the current period and dim past ones in the
+                          printed schedule (default: auto, only on a terminal)
+    --data <dir>          Schedule directory with meta.json, calendar.bell and schedules.bell
+                          (default: $XDG_CONFIG_HOME/bell, then /etc/bell, then the
+                          schedule built into the binary), or the same files bundled
+                          as a .tar.gz or .zip (requires the `archive` feature)
+    --school-file <path>  File naming the selected school, re-read whenever it changes;
+                          the school is a subdirectory of $XDG_CONFIG_HOME/bell or
+                          /etc/bell (default: $XDG_STATE_HOME/bell/school)
+    --default-school <name>
+                          School to use when the selected one does not exist
+    --at <time>           Evaluate at another moment: \"+90m\" or \"-2h\" from now, \"14:05\"
+                          today or \"2026-09-02T08:50\"
+    --tz <zone>           IANA timezone to evaluate the schedule in, e.g. America/Los_Angeles
+                          (default: meta.json \"timezone\", else the local timezone)
+    --sleep-hint          Print how many seconds the --format output stays the same, for
+                          pollers that want to sleep until it changes, then exit
+    --follow              Instead of a live line, print one timestamped line per period
+                          change, e.g. \"2025-09-02T08:50:00 -> Chemistry\"
+    --interval <secs>     Refresh interval for continuous mode (default: 1); drops to
+                          1 second once the remaining time is below --alert-below
+    --count <n>           Exit after printing n updates instead of running forever
+    --max-lookahead <days>
+                          Days, counting today, searched for the next bell, e.g. 14
+                          to show nothing over a long break (default: 366)
+    --rotate-every <secs> How long each message of a period written as
+                          \"10:00 Announcements | Picture day\" is shown (default: 10)
+    --sync second|minute  Redraw on every tick (default) or exactly on each whole minute,
+                          for minute-only formats
+    --adaptive-precision <secs>
+                          Show the plain-format countdown in whole minutes (\"23m\") and
+                          switch to seconds (\"42s\") below this many seconds, e.g. 60;
+                          a --format pattern shows it through [Adaptive]
+    --duration-width <n>  Right-align the plain-format countdown in n characters so the
+                          redrawn line keeps its length, e.g. 8 for \"10:00:00\"
+    --separator <text>    Plain-format separator between period and countdown (default: \" | \")
+    --label-separator <text>
+                          Plain-format separator after a label or wording (default: \": \")
+    --ends-in-text <text> Plain-format wording for the current period (default: \"Ends in\")
+    --starts-in-text <text>
+                          Plain-format wording for an upcoming period (default: \"Starts in\")
+    --count-from start|end
+                          Show the time since the current period started instead of
+                          the time until it ends (default: end)
+    --round up|down|nearest
+                          Rounding of the smallest unit shown by a pattern (default: down)
+    --snapshot-round up|down|nearest
+                          Rounding of the sub-second part of the --once countdown
+                          (default: down)
+    --warn-below <secs>   Remaining time that counts as a warning (default: 300)
+    --alert-below <secs>  Remaining time that counts as an alert (default: 60)
+    --color-thresholds <secs>=<color>,...
+                          Countdown colors for the tmux and i3blocks formats, e.g.
+                          \"600=yellow,300=orange,60=red\"; the smallest threshold above
+                          the remaining time wins (default: from --warn-below/--alert-below)
+    --boundary inclusive|exclusive
+                          Whether the bell moment starts the new period (inclusive, default)
+                          or still belongs to the ending one (exclusive)
+    --transition-grace <secs>
+                          Keep showing \"Next\" for this long after a period starts (default: 0)
+    --override <name>     Use this schedule for today instead of the calendar's, e.g. for
+                          a drill
+    --override-file <path>
+                          Same, for as long as the file names a schedule; re-read on every
+                          tick, so emptying or removing it restores the calendar
+    --merge-adjacent      Treat consecutive periods with the same name as one period
+    --only-during-school  Show \"No school\" instead of a countdown outside today's
+                          first and last bell
+    --gap-behavior hold|advance
+                          When a period runs down to 00:00 before the next one has
+                          started, keep showing the ended period (hold, default) or
+                          switch to the next one (advance); --transition-grace still
+                          shows \"Next\" during its window
+    --ring-command <cmd>  Run a shell command at every bell, e.g. to drive a wired bell;
+                          BELL_PERIOD holds the period that starts. Periods written as
+                          \"silent 12:00 Lunch\" in schedules.bell do not ring
+    --notify              Show a desktop notification when the period changes
+                          (requires the `notify` feature)
+    --notify-urgency <u>  Notification urgency: low, normal, critical (implies --notify)
+    --notify-timeout <t>  Notification timeout: 10s, 500ms, default, never (implies --notify)
+    --notify-day-over <text>
+                          Text of the once-a-day notification after the last bell
+                          (default: \"School day over\", implies --notify)
+    --quiet-hours <from>-<to>
+                          Hold back notifications during this window, e.g. 22:00-06:00;
+                          the display keeps updating (implies --notify)
+    --mqtt <broker-url>   Publish period changes as JSON to an MQTT broker
+                          (requires the `mqtt` feature)
+    --mqtt-topic <topic>  Topic for --mqtt (default: bell/current)
+    --output-file <path>  Also keep the latest line in this file
+    --output-format <f>   Format written to --output-file (default: json)"
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DayState {
+    BeforeSchool,
+    Passing,
+    InClass,
+    AfterSchool,
+}
+
+impl DayState {
+    fn as_str(self) -> &'static str {
+        match self {
+            DayState::BeforeSchool => "before-school",
+            DayState::Passing => "passing",
+            DayState::InClass => "in-class",
+            DayState::AfterSchool => "after-school",
+        }
+    }
+}
+
+struct Snapshot {
+    label: &'static str,
+    msg: String,
+    remaining: time::Duration,
+    state: DayState,
+}
+
+fn snapshot(data: &data::AppData, opts: &Options) -> Option<Snapshot> {
+    if opts.only_during_school {
+        let now_dt = data.now();
+        let bounds = data.day_bounds(now_dt.date());
+        if !data.in_session(now_dt.date(), now_dt.time()) {
+            let state = match bounds {
+                Some((first, _)) if now_dt.time() < first => DayState::BeforeSchool,
+                _ => DayState::AfterSchool,
+            };
+            return Some(Snapshot {
+                label: "Free",
+                msg: "No school".to_string(),
+                remaining: time::Duration::ZERO,
+                state,
+            });
+        }
+    }
+    let mut snap = current_or_next(data, opts.transition_grace, opts.gap)?;
+    if let Some(index) = opts.until_index {
+        let now_dt = data.now();
+        let period = data.visible_periods(now_dt.date())?.get(index - 1)?.clone();
+        snap.label = "Until";
+        snap.remaining =
+            until_time(data, now_dt, now_dt.date(), period.start).max(time::Duration::ZERO);
+        snap.msg = period.msg;
+    }
+    Some(snap)
+}
+
+fn current_or_next(
+    data: &data::AppData,
+    grace: time::Duration,
+    gap: GapBehavior,
+) -> Option<Snapshot> {
+    let now_dt = data.now();
+    let today = now_dt.date();
+    let now = now_dt.time();
+    // Periods that started less than `grace` ago are still reported as upcoming.
+    let shifted_dt = now_dt - grace;
+    let (label, msg, remaining, state) =
+        match data.current_section(shifted_dt.date(), shifted_dt.time()) {
+            // After a final period with a set end, the next bell comes from a later day.
+            Some(section)
+                if section
+                    .current_period_end
+                    .is_some_and(|end| end > now || section.next_period.is_some()) =>
+            {
+                let end = section.current_period_end.unwrap();
+                if end > now {
+                    let state = if data.meta.is_break(&section.current_period.msg) {
+                        DayState::Passing
+                    } else {
+                        DayState::InClass
+                    };
+                    ("Current", section.current_period.msg, end - now, state)
+                } else {
+                    let next = section.next_period.unwrap();
+                    let until_next = (next.start - now).max(time::Duration::ZERO);
+                    match gap {
+                        // Inside the grace window the period that just started is still "Next".
+                        _ if !grace.is_zero() => ("Next", next.msg, until_next, DayState::Passing),
+                        // A gap without grace: the period ran down to 00:00 on the bell itself.
+                        GapBehavior::Hold => (
+                            "Current",
+                            section.current_period.msg,
+                            time::Duration::ZERO,
+                            DayState::Passing,
+                        ),
+                        GapBehavior::Advance => ("Next", next.msg, until_next, DayState::Passing),
+                    }
+                }
+            }
+            _ if data.free_day_name(today).is_some() => {
+                let name = data.free_day_name(today).unwrap().to_string();
+                let remaining = next_period_from(data, now_dt)
+                    .map(|(_, remaining)| remaining)
+                    .unwrap_or(time::Duration::ZERO);
+                ("Free", name, remaining, DayState::AfterSchool)
+            }
+            _ => match next_period_from(data, shifted_dt) {
+                Some((period, remaining)) => {
+                    let remaining = (remaining - grace).max(time::Duration::ZERO);
+                    let starts_on = (now_dt + remaining).date();
+                    if starts_on == today {
+                        // Later periods of the day are reached through current_section, so
+                        // this is the wait for the first one.
+                        ("Next", period.msg, remaining, DayState::BeforeSchool)
+                    } else if Some(starts_on) == today.next_day() {
+                        ("Tomorrow", period.msg, remaining, DayState::AfterSchool)
+                    } else {
+                        let msg = format!("{} ({})", period.msg, starts_on.weekday());
+                        ("Next", msg, remaining, DayState::AfterSchool)
+                    }
+                }
+                None if !data.in_school_year(today) => (
+                    "Free",
+                    "No school".to_string(),
+                    time::Duration::ZERO,
+                    DayState::AfterSchool,
+                ),
+                None => return None,
+            },
+        };
+    Some(Snapshot {
+        label,
+        msg,
+        remaining,
+        state,
+    })
+}
+
+fn render_line(
+    opts: &Options,
+    data: &data::AppData,
+    format: &OutputFormat,
+    snap: &Snapshot,
+    separator: &str,
+) -> String {
+    let rotated = rotated_msg(opts, data, snap);
+    let (label, msg, remaining) = (snap.label, rotated.as_str(), snap.remaining);
+    // Urgency always follows the real remaining time, only the shown duration changes.
+    let shown = match opts.count_from {
+        CountFrom::Start if label == "Current" => progress(data).0,
+        _ => remaining,
+    };
+    match format {
+        OutputFormat::Plain => default_line(opts, label, msg, shown),
+        OutputFormat::NowNext => now_next_lines(data).join(separator),
+        OutputFormat::Tmux => {
+            let color = countdown_color(opts, remaining, ["green", "yellow", "red"]);
+            tmux_line(label, msg, shown, color)
+        }
+        OutputFormat::I3blocks => {
+            i3blocks_lines(opts, label, msg, remaining, shown).join(separator)
+        }
+        OutputFormat::Json => json_line(label, msg, remaining),
+        OutputFormat::Binary => unreachable!("binary frames are written by Sink::write_frame"),
+        OutputFormat::Pattern(pattern) => {
+            let today = data.now().date();
+            let (elapsed, percent) = progress(data);
+            let tokens = PatternTokens {
+                label,
+                state: snap.state.as_str(),
+                period: msg,
+                icon: data.meta.icon_for(msg),
+                comment: data.day_comment(today).unwrap_or_default(),
+                next_bell: next_bell(data).map(clock_time).unwrap_or_default(),
+                zone: data.zone_for_date(today).unwrap_or_default(),
+                meta_name: &data.meta.name,
+                schedule: data.schedule_name_for_date(today).unwrap_or_default(),
+                week: data.week_index(today),
+                remaining: shown,
+                elapsed,
+                percent,
+                day_percent: day_percent(data),
+                adaptive: adaptive_duration(
+                    shown,
+                    opts.adaptive_below.unwrap_or(DEFAULT_ADAPTIVE_BELOW),
+                ),
+                next_change_in: next_change_in(pattern, remaining, opts.round),
+            };
+            format_line_with_pattern(pattern, &tokens, opts.round)
+        }
+    }
+}
+
+const FRAME_MSG_LEN: usize = 32;
+
+// Fixed 35-byte frame: the state (0 before school, 1 passing, 2 in class, 3 after
+// school), the whole minutes left as a big-endian u16, then the period as UTF-8,
+// cut at a character boundary to at most 32 bytes and padded with zero bytes.
+fn binary_frame(opts: &Options, data: &data::AppData, snap: &Snapshot) -> Vec<u8> {
+    let state = match snap.state {
+        DayState::BeforeSchool => 0,
+        DayState::Passing => 1,
+        DayState::InClass => 2,
+        DayState::AfterSchool => 3,
+    };
+    let minutes = snap.remaining.whole_minutes().clamp(0, i64::from(u16::MAX)) as u16;
+    let msg = rotated_msg(opts, data, snap);
+    let mut end = msg.len().min(FRAME_MSG_LEN);
+    while !msg.is_char_boundary(end) {
+        end -= 1;
+    }
+    let mut frame = vec![state];
+    frame.extend(minutes.to_be_bytes());
+    frame.extend(&msg.as_bytes()[..end]);
+    frame.resize(3 + FRAME_MSG_LEN, 0);
+    frame
+}
+
+#[derive(Serialize)]
+struct PeriodState<'a> {
+    label: &'a str,
+    period: &'a str,
+    remaining_secs: i64,
+}
+
+pub fn json_line(label: &str, msg: &str, remaining: time::Duration) -> String {
+    let state = PeriodState {
+        label,
+        period: msg,
+        remaining_secs: remaining.whole_seconds().max(0),
+    };
+    serde_json::to_string(&state).expect("Failed to serialize period state")
+}
+
+fn urgency_for(opts: &Options, remaining: time::Duration) -> Urgency {
+    if remaining < opts.alert_below {
+        Urgency::Alert
+    } else if remaining < opts.warn_below {
+        Urgency::Warning
+    } else {
+        Urgency::Normal
+    }
 }
 
-fn current_or_next(data: &data::AppData) -> Option<(&'static str, String, time::Duration)> {
-    let now_dt = time::OffsetDateTime::now_local().unwrap();
+// User thresholds pick the tightest one still above the remaining time; without them
+// the palette's normal, warning and alert colors follow --warn-below/--alert-below.
+fn countdown_color<'a>(
+    opts: &'a Options,
+    remaining: time::Duration,
+    palette: [&'a str; 3],
+) -> &'a str {
+    if !opts.color_thresholds.is_empty() {
+        return opts
+            .color_thresholds
+            .iter()
+            .find(|(below, _)| remaining < *below)
+            .map_or(palette[0], |(_, color)| color.as_str());
+    }
+    match urgency_for(opts, remaining) {
+        Urgency::Normal => palette[0],
+        Urgency::Warning => palette[1],
+        Urgency::Alert => palette[2],
+    }
+}
+
+fn tmux_line(label: &str, msg: &str, remaining: time::Duration, color: &str) -> String {
+    let text = if label == "Current" {
+        format!("{} {}", msg, short_duration(remaining))
+    } else {
+        format!("{}: {} {}", label, msg, short_duration(remaining))
+    };
+    format!("#[fg={}]{}#[default]", color, text.replace('#', "##"))
+}
+
+fn i3blocks_lines(
+    opts: &Options,
+    label: &str,
+    msg: &str,
+    remaining: time::Duration,
+    shown: time::Duration,
+) -> [String; 3] {
+    let color = countdown_color(opts, remaining, ["", "#FFFF00", "#FF0000"]);
+    [
+        default_line(opts, label, msg, shown),
+        format!("{} {}", msg, short_duration(shown)),
+        color.to_string(),
+    ]
+}
+
+fn now_next_lines(data: &data::AppData) -> Vec<String> {
+    let now_dt = data.now();
     let today = now_dt.date();
     let now = now_dt.time();
-    let (label, msg, remaining) = match data.current_section(today, now) {
+    let mut lines = Vec::new();
+    match data.current_section(today, now) {
         Some(section) if section.current_period_end.is_some() => {
             let end = section.current_period_end.unwrap();
             let remaining = if end > now {
@@ -126,23 +1783,97 @@ fn current_or_next(data: &data::AppData) -> Option<(&'static str, String, time::
             } else {
                 time::Duration::ZERO
             };
-            ("Current", section.current_period.msg, remaining)
+            lines.push(format!(
+                "Now: {} ({})",
+                section.current_period.msg,
+                short_duration(remaining)
+            ));
+            if let Some(next) = section.next_period {
+                lines.push(format!("Next: {} at {}", next.msg, clock_time(next.start)));
+            }
         }
-        _ => match next_period_from(data, now_dt) {
-            Some((period, remaining)) => ("Next", period.msg, remaining),
-            None => return None,
-        },
-    };
-    Some((label, msg, remaining))
+        _ => {
+            if let Some((period, remaining)) = next_period_from(data, now_dt) {
+                let at = now_dt + remaining;
+                let when = if at.date() == today {
+                    clock_time(at.time())
+                } else {
+                    format!("{:.3} {}", at.weekday().to_string(), clock_time(at.time()))
+                };
+                lines.push(format!("Next: {} at {}", period.msg, when));
+            }
+        }
+    }
+    lines
 }
 
-fn default_line(label: &str, msg: &str, remaining: time::Duration) -> String {
-    format!(
-        "{}: {} | Remaining: {}",
-        label,
-        msg,
-        format_duration(remaining),
-    )
+fn short_duration(duration: time::Duration) -> String {
+    let total_minutes = duration.whole_minutes().max(0);
+    if total_minutes >= 60 {
+        format!("{}h{:02}m", total_minutes / 60, total_minutes % 60)
+    } else {
+        format!("{}m", total_minutes)
+    }
+}
+
+// Whole minutes ("23m") until less than `below` is left, then seconds ("42s").
+const DEFAULT_ADAPTIVE_BELOW: time::Duration = time::Duration::seconds(60);
+
+fn adaptive_duration(duration: time::Duration, below: time::Duration) -> String {
+    let secs = duration.whole_seconds().max(0);
+    if duration < below {
+        format!("{secs}s")
+    } else {
+        format!("{}m", secs / 60)
+    }
+}
+
+// ISO 8601 duration such as PT1H5M9S; zero components are left out.
+fn iso_duration(duration: time::Duration) -> String {
+    let total = duration.whole_seconds().max(0);
+    if total == 0 {
+        return "PT0S".to_string();
+    }
+    let mut out = "PT".to_string();
+    for (value, unit) in [
+        (total / 3600, 'H'),
+        (total % 3600 / 60, 'M'),
+        (total % 60, 'S'),
+    ] {
+        if value > 0 {
+            out.push_str(&format!("{value}{unit}"));
+        }
+    }
+    out
+}
+
+fn clock_time(time: time::Time) -> String {
+    format!("{:02}:{:02}", time.hour(), time.minute())
+}
+
+fn default_line(opts: &Options, label: &str, msg: &str, remaining: time::Duration) -> String {
+    let phrase = match label {
+        "Current" if matches!(opts.count_from, CountFrom::Start) => "Elapsed",
+        "Current" => opts.ends_in_text.as_str(),
+        "Next" | "Tomorrow" | "Until" => opts.starts_in_text.as_str(),
+        _ => "Remaining",
+    };
+    let (sep, label_sep) = (&opts.separator, &opts.label_separator);
+    let duration = match opts.adaptive_below {
+        Some(below) => adaptive_duration(remaining, below),
+        None => format_duration(remaining),
+    };
+    let countdown = format!(
+        "{}{}{:>width$}",
+        phrase,
+        label_sep,
+        duration,
+        width = opts.duration_width
+    );
+    if opts.no_label {
+        return format!("{msg}{sep}{countdown}");
+    }
+    format!("{label}{label_sep}{msg}{sep}{countdown}")
 }
 
 fn print_line(line: String, newline: bool) {
@@ -162,15 +1893,38 @@ fn format_duration(duration: time::Duration) -> String {
 }
 
 fn format_duration_with_pattern(duration: time::Duration, pattern: &str) -> String {
-    let (hours, minutes, seconds) = duration_tokens(duration, pattern);
-    pattern
-        .replace("[HH]", &hours)
-        .replace("[MM]", &minutes)
-        .replace("[SS]", &seconds)
+    duration_tokens(duration, pattern, Rounding::Down).fill(pattern)
+}
+
+struct DurationTokens {
+    hours: i64,
+    minutes: i64,
+    seconds: i64,
+}
+
+impl DurationTokens {
+    // [MM]/[SS] are zero-padded, [m]/[s] are not.
+    fn fill(&self, text: &str) -> String {
+        text.replace("[HH]", &self.hours.to_string())
+            .replace("[MM]", &format!("{:02}", self.minutes))
+            .replace("[SS]", &format!("{:02}", self.seconds))
+            .replace("[m]", &self.minutes.to_string())
+            .replace("[s]", &self.seconds.to_string())
+    }
 }
 
-fn duration_tokens(duration: time::Duration, pattern: &str) -> (String, String, String) {
-    let total_seconds = duration.whole_seconds().max(0);
+fn duration_tokens(duration: time::Duration, pattern: &str, round: Rounding) -> DurationTokens {
+    let has_minutes = pattern.contains("[MM]") || pattern.contains("[m]");
+    let unit = if pattern.contains("[SS]") || pattern.contains("[s]") {
+        1
+    } else if has_minutes {
+        60
+    } else if pattern.contains("[HH]") {
+        3600
+    } else {
+        1
+    };
+    let total_seconds = round_to_unit(duration.whole_seconds().max(0), unit, round);
     let mut hours = total_seconds / 3600;
     let mut minutes = (total_seconds % 3600) / 60;
     let mut seconds = total_seconds % 60;
@@ -178,60 +1932,536 @@ fn duration_tokens(duration: time::Duration, pattern: &str) -> (String, String,
         minutes += hours * 60;
         hours = 0;
     }
-    if !pattern.contains("[MM]") {
+    if !has_minutes {
         seconds += minutes * 60;
         minutes = 0;
     }
-    (
-        hours.to_string(),
-        format!("{:02}", minutes),
-        format!("{:02}", seconds),
-    )
+    DurationTokens {
+        hours,
+        minutes,
+        seconds,
+    }
 }
 
-fn format_line_with_pattern(
-    pattern: &str,
-    label: &str,
-    period: &str,
+// A --once snapshot taken mid-second would otherwise always read up to a second short.
+fn round_subsecond(duration: time::Duration, round: Rounding) -> time::Duration {
+    let seconds = duration.whole_seconds();
+    let nanos = duration.subsec_nanoseconds();
+    let seconds = match round {
+        Rounding::Down => seconds,
+        Rounding::Up if nanos > 0 => seconds + 1,
+        Rounding::Up => seconds,
+        Rounding::Nearest if nanos >= 500_000_000 => seconds + 1,
+        Rounding::Nearest => seconds,
+    };
+    time::Duration::seconds(seconds)
+}
+
+fn round_to_unit(seconds: i64, unit: i64, round: Rounding) -> i64 {
+    let rounded = match round {
+        Rounding::Down => seconds / unit,
+        Rounding::Up => (seconds + unit - 1) / unit,
+        Rounding::Nearest => (seconds + unit / 2) / unit,
+    };
+    rounded * unit
+}
+
+const PATTERN_TOKENS: &[&str] = &[
+    "[Label]",
+    "[State]",
+    "[Period]",
+    "[Icon]",
+    "[Comment]",
+    "[NextBell]",
+    "[Zone]",
+    "[MetaName]",
+    "[Schedule]",
+    "[Week]",
+    "[TotalSeconds]",
+    "[ISO]",
+    "[Elapsed]",
+    "[Percent]",
+    "[DayPercent]",
+    "[NextChangeIn]",
+    "[Adaptive]",
+    "[HH]",
+    "[MM]",
+    "[SS]",
+    "[m]",
+    "[s]",
+];
+
+fn explain_pattern(pattern: &str) -> String {
+    let mut out = String::new();
+    let mut rest = pattern;
+    while let Some(close) = rest.find(']') {
+        let Some(open) = rest[..close].rfind('[') else {
+            rest = &rest[close + 1..];
+            continue;
+        };
+        let token = &rest[open..=close];
+        if PATTERN_TOKENS.contains(&token) {
+            out.push_str(&format!("{token}  token\n"));
+        } else {
+            out.push_str(&format!("{token}  unknown, left as literal text\n"));
+        }
+        rest = &rest[close + 1..];
+    }
+    if out.is_empty() {
+        out.push_str("No tokens found\n");
+    }
+    out
+}
+
+// Seconds until the rendered pattern would look different: the next second or minute
+// when it shows those, otherwise the next bell.
+fn next_change_in(pattern: &str, remaining: time::Duration, round: Rounding) -> i64 {
+    let secs = remaining.whole_seconds().max(0);
+    let uses = |tokens: &[&str]| tokens.iter().any(|token| pattern.contains(token));
+    // A rounded unit flips when `secs` plus this offset crosses a multiple of the unit.
+    let until_flip = |unit: i64| {
+        let offset = match round {
+            Rounding::Down => 0,
+            Rounding::Up => unit - 1,
+            Rounding::Nearest => unit / 2,
+        };
+        (secs + offset) % unit + 1
+    };
+    let change = if uses(&[
+        "[SS]",
+        "[s]",
+        "[TotalSeconds]",
+        "[ISO]",
+        "[Elapsed]",
+        "[Percent]",
+        "[DayPercent]",
+        "[Adaptive]",
+    ]) {
+        1
+    } else if uses(&["[MM]", "[m]"]) {
+        until_flip(60)
+    } else if uses(&["[HH]"]) {
+        until_flip(3600)
+    } else {
+        secs
+    };
+    change.min(secs).max(1)
+}
+
+struct PatternTokens<'a> {
+    label: &'a str,
+    state: &'a str,
+    period: &'a str,
+    icon: &'a str,
+    comment: &'a str,
+    next_bell: String,
+    zone: &'a str,
+    meta_name: &'a str,
+    schedule: &'a str,
+    week: u8,
     remaining: time::Duration,
-) -> String {
-    let (hours, minutes, seconds) = duration_tokens(remaining, pattern);
-    pattern
-        .replace("[Label]", label)
-        .replace("[Period]", period)
-        .replace("[HH]", &hours)
-        .replace("[MM]", &minutes)
-        .replace("[SS]", &seconds)
+    elapsed: time::Duration,
+    percent: i64,
+    day_percent: i64,
+    next_change_in: i64,
+    adaptive: String,
+}
+
+fn format_line_with_pattern(pattern: &str, tokens: &PatternTokens, round: Rounding) -> String {
+    let line = pattern
+        .replace("[Label]", tokens.label)
+        .replace("[State]", tokens.state)
+        .replace("[Period]", tokens.period)
+        .replace("[Icon]", tokens.icon)
+        .replace("[Comment]", tokens.comment)
+        .replace("[NextBell]", &tokens.next_bell)
+        .replace("[Zone]", tokens.zone)
+        .replace("[MetaName]", tokens.meta_name)
+        .replace("[Schedule]", tokens.schedule)
+        .replace("[Week]", &tokens.week.to_string())
+        .replace(
+            "[TotalSeconds]",
+            &tokens.remaining.whole_seconds().max(0).to_string(),
+        )
+        .replace("[ISO]", &iso_duration(tokens.remaining))
+        .replace("[Elapsed]", &format_duration(tokens.elapsed))
+        .replace("[Percent]", &tokens.percent.to_string())
+        .replace("[DayPercent]", &tokens.day_percent.to_string())
+        .replace("[NextChangeIn]", &tokens.next_change_in.to_string())
+        .replace("[Adaptive]", &tokens.adaptive);
+    duration_tokens(tokens.remaining, pattern, round).fill(&line)
+}
+
+fn peek_line(data: &data::AppData) -> String {
+    let now_dt = data.now();
+    let next_today = data
+        .current_section(now_dt.date(), now_dt.time())
+        .and_then(|section| section.next_period);
+    let (date, period) = match next_today {
+        Some(period) => (now_dt.date(), period),
+        None => match next_period_from(data, now_dt) {
+            Some((period, remaining)) => ((now_dt + remaining).date(), period),
+            None => return "No upcoming bells.".to_string(),
+        },
+    };
+    let day = if date == now_dt.date() {
+        String::new()
+    } else {
+        format!("{} ", &date.weekday().to_string()[..3])
+    };
+    format!(
+        "Next bell: {}{} — start of {}.",
+        day,
+        clock_time(period.start),
+        period.msg
+    )
+}
+
+// The end of the current period, or else the start of the next one.
+fn next_bell(data: &data::AppData) -> Option<time::Time> {
+    let now_dt = data.now();
+    data.current_section(now_dt.date(), now_dt.time())
+        .and_then(|section| section.current_period_end)
+        .or_else(|| next_period_from(data, now_dt).map(|(period, _)| period.start))
+}
+
+// A current period with several messages shows each for --rotate-every seconds.
+fn rotated_msg(opts: &Options, data: &data::AppData, snap: &Snapshot) -> String {
+    if snap.label != "Current" {
+        return snap.msg.clone();
+    }
+    let now_dt = data.now();
+    let Some(section) = data.current_section(now_dt.date(), now_dt.time()) else {
+        return snap.msg.clone();
+    };
+    let rotation = &section.current_period.rotation;
+    if rotation.is_empty() {
+        return snap.msg.clone();
+    }
+    let elapsed = (now_dt.time() - section.current_period.start)
+        .whole_seconds()
+        .max(0) as u64;
+    let slot = elapsed / opts.rotate_every_secs.max(1);
+    rotation[slot as usize % rotation.len()].clone()
+}
+
+// Time since the current period started, or since the day start before the first
+// bell, together with how much of that span has passed in percent.
+fn progress(data: &data::AppData) -> (time::Duration, i64) {
+    let now_dt = data.now();
+    let (today, now) = (now_dt.date(), now_dt.time());
+    if let Some(section) = data.current_section(today, now) {
+        return match section.current_period_end {
+            Some(end) => span_progress(section.current_period.start, end, now),
+            None => (time::Duration::ZERO, 0),
+        };
+    }
+    let first = data
+        .periods_for_date(today)
+        .and_then(|periods| periods.first().map(|period| period.start));
+    match (data.day_start(today), first) {
+        (Some(start), Some(first)) => span_progress(start, first, now),
+        _ => (time::Duration::ZERO, 0),
+    }
+}
+
+// How far through the school day (first bell to the day's end) it is: 0 before it,
+// 100 after.
+fn day_percent(data: &data::AppData) -> i64 {
+    let now_dt = data.now();
+    match data.day_bounds(now_dt.date()) {
+        Some((first, last)) => span_progress(first, last, now_dt.time()).1,
+        None => 0,
+    }
+}
+
+fn span_progress(start: time::Time, end: time::Time, now: time::Time) -> (time::Duration, i64) {
+    let total = end - start;
+    if !total.is_positive() {
+        return (time::Duration::ZERO, 0);
+    }
+    let elapsed = (now - start).clamp(time::Duration::ZERO, total);
+    (
+        elapsed,
+        elapsed.whole_seconds() * 100 / total.whole_seconds(),
+    )
 }
 
+const NEXT_PERIOD_SEARCH_DAYS: u32 = 366;
+
 fn next_period_from(
     data: &data::AppData,
     now_dt: time::OffsetDateTime,
 ) -> Option<(data::Period, time::Duration)> {
-    let offset = now_dt.offset();
     let mut date = now_dt.date();
-    loop {
-        let schedule_name = match data.schedule_name_for_date(date) {
-            Some(name) => name,
+    // Weekends and holidays without periods are skipped; without a year_end the
+    // search still gives up after --max-lookahead days (default: a year) instead of
+    // walking to Date::MAX.
+    let days = data.max_lookahead.unwrap_or(NEXT_PERIOD_SEARCH_DAYS);
+    for _ in 0..days {
+        if data.meta.year_end.is_some_and(|end| date > end) {
+            return None;
+        }
+        let periods = match data.visible_periods(date) {
+            Some(periods) => periods,
             None => {
                 date = date.next_day()?;
                 continue;
             }
         };
-        let schedule = data.schedules.schedules.get(schedule_name)?;
-        let first = match schedule.periods.first() {
+        let first = match periods.first() {
             Some(period) => period.clone(),
             None => {
                 date = date.next_day()?;
                 continue;
             }
         };
-        if date == now_dt.date() && now_dt.time() >= first.start {
+        if date == now_dt.date() && data.boundary.has_started(first.start, now_dt.time()) {
             date = date.next_day()?;
             continue;
         }
-        let target = PrimitiveDateTime::new(date, first.start).assume_offset(offset);
-        let remaining = target - now_dt;
+        let remaining = until_time(data, now_dt, date, first.start);
         return Some((first, remaining));
     }
+    None
+}
+
+// The target takes the offset of its own date, so a countdown over a DST change is exact.
+fn until_time(
+    data: &data::AppData,
+    now_dt: time::OffsetDateTime,
+    date: time::Date,
+    at: time::Time,
+) -> time::Duration {
+    data.local_datetime(date, at) - now_dt
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{date, datetime, time};
+
+    use super::*;
+    use crate::data::tests::{fixture, pin_now};
+
+    // `now()` keeps running after pinning, so allow for the time the test takes.
+    fn assert_about(remaining: time::Duration, expected: time::Duration) {
+        let drift = expected - remaining;
+        assert!(
+            drift >= time::Duration::ZERO && drift < time::Duration::seconds(1),
+            "{remaining} is not about {expected}"
+        );
+    }
+
+    #[test]
+    fn friday_evening_rolls_over_the_weekend() {
+        let data = fixture("basic");
+        assert!(data.periods_for_date(date!(2026 - 10 - 17)).is_none());
+        let friday = datetime!(2026-10-16 18:00 UTC);
+        let (period, remaining) = next_period_from(&data, friday).unwrap();
+        assert_eq!(period.msg, "Period 1");
+        assert_eq!(period.start, time!(08:00));
+        // Monday 08:00 is 2 days and 14 hours later.
+        assert_eq!(remaining, time::Duration::hours(62));
+    }
+
+    #[test]
+    fn short_format_rolls_hours_into_minutes() {
+        let OutputFormat::Pattern(pattern) = parse_format("short".to_string()) else {
+            panic!("short is a pattern preset");
+        };
+        let tokens = duration_tokens(time::Duration::minutes(90), &pattern, Rounding::Down);
+        assert_eq!(tokens.fill(&pattern), "[Period] 90m");
+        let tokens = duration_tokens(time::Duration::seconds(59), &pattern, Rounding::Down);
+        assert_eq!(tokens.fill(&pattern), "[Period] 00m");
+    }
+
+    #[test]
+    fn after_school_on_friday_counts_down_to_monday() {
+        let mut data = fixture("basic");
+        pin_now(&mut data, datetime!(2026-10-16 18:00));
+        let snap = current_or_next(&data, time::Duration::ZERO, GapBehavior::Hold).unwrap();
+        assert_eq!(snap.label, "Next");
+        assert_eq!(snap.msg, "Period 1 (Monday)");
+        assert_eq!(snap.state, DayState::AfterSchool);
+        assert_about(snap.remaining, time::Duration::hours(62));
+    }
+
+    #[test]
+    fn after_school_before_a_school_day_says_tomorrow() {
+        let mut data = fixture("basic");
+        pin_now(&mut data, datetime!(2026-10-15 18:00));
+        let snap = current_or_next(&data, time::Duration::ZERO, GapBehavior::Hold).unwrap();
+        assert_eq!(snap.label, "Tomorrow");
+        assert_eq!(snap.msg, "Period 1");
+        assert_about(snap.remaining, time::Duration::hours(14));
+    }
+
+    #[test]
+    fn padded_and_unpadded_tokens() {
+        let remaining = time::Duration::seconds(5 * 60 + 3);
+        assert_eq!(
+            format_duration_with_pattern(remaining, "[MM]:[SS]"),
+            "05:03"
+        );
+        assert_eq!(format_duration_with_pattern(remaining, "[m]m[s]s"), "5m3s");
+    }
+
+    #[test]
+    fn iso_duration_leaves_out_zero_components() {
+        assert_eq!(iso_duration(time::Duration::hours(2)), "PT2H");
+        assert_eq!(iso_duration(time::Duration::minutes(5)), "PT5M");
+        assert_eq!(iso_duration(time::Duration::seconds(9)), "PT9S");
+        assert_eq!(iso_duration(time::Duration::ZERO), "PT0S");
+        assert_eq!(iso_duration(time::Duration::seconds(3909)), "PT1H5M9S");
+    }
+
+    #[test]
+    fn meta_name_and_schedule_render_distinctly() {
+        let mut data = fixture("basic");
+        pin_now(&mut data, datetime!(2026-10-14 09:00));
+        let opts = parse_args(
+            ["--format", "[MetaName]/[Schedule]"]
+                .map(String::from)
+                .into_iter(),
+        )
+        .unwrap();
+        let snap = current_or_next(&data, time::Duration::ZERO, GapBehavior::Hold).unwrap();
+        let line = render_line(&opts, &data, &opts.format, &snap, "\n");
+        assert_eq!(line, "Fixture High/regular");
+    }
+
+    #[test]
+    fn grace_window_shows_the_next_period() {
+        let mut data = fixture("basic");
+        pin_now(&mut data, datetime!(2026-10-14 08:50:30));
+        let grace = time::Duration::minutes(1);
+        for gap in [GapBehavior::Hold, GapBehavior::Advance] {
+            let snap = current_or_next(&data, grace, gap).unwrap();
+            assert_eq!(snap.label, "Next");
+            assert_eq!(snap.msg, "Period 2");
+            assert_eq!(snap.remaining, time::Duration::ZERO);
+            assert_eq!(snap.state, DayState::Passing);
+        }
+        // Once the grace has passed, Period 2 is current.
+        pin_now(&mut data, datetime!(2026-10-14 08:51:30));
+        let snap = current_or_next(&data, grace, GapBehavior::Hold).unwrap();
+        assert_eq!((snap.label, snap.msg.as_str()), ("Current", "Period 2"));
+        assert_about(snap.remaining, time::Duration::seconds(58 * 60 + 30));
+    }
+
+    #[test]
+    fn hidden_periods_are_not_announced() {
+        let mut data = fixture("basic");
+        pin_now(&mut data, datetime!(2026-10-21 08:20));
+        let lines = now_next_lines(&data);
+        assert_eq!(lines[1], "Next: Period 2 at 08:50");
+        assert_eq!(peek_line(&data), "Next bell: 08:50 — start of Period 2.");
+        pin_now(&mut data, datetime!(2026-10-21 08:40));
+        let snap = current_or_next(&data, time::Duration::ZERO, GapBehavior::Hold).unwrap();
+        assert_eq!((snap.label, snap.msg.as_str()), ("Current", "Period 1"));
+    }
+
+    #[test]
+    fn countdown_over_a_dst_change() {
+        let mut data = fixture("basic");
+        data.meta.timezone = Some("America/Los_Angeles".to_string());
+        // Clocks fall back on Sunday 2026-11-01, so the weekend is an hour longer.
+        let friday = datetime!(2026-10-30 18:00 -07:00);
+        let (period, remaining) = next_period_from(&data, friday).unwrap();
+        assert_eq!(period.start, time!(08:00));
+        assert_eq!(remaining, time::Duration::hours(63));
+    }
+
+    #[test]
+    fn at_uses_the_offset_of_the_target_date() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/basic");
+        let args = [
+            "--data",
+            dir,
+            "--tz",
+            "America/Los_Angeles",
+            "--at",
+            "2026-12-14T12:30",
+        ];
+        let opts = parse_args(args.map(String::from).into_iter()).unwrap();
+        let (data, _) = load_data(&opts, None).unwrap();
+        let now = data.now();
+        assert_eq!(now.date(), date!(2026 - 12 - 14));
+        assert_eq!((now.hour(), now.minute()), (12, 30));
+        assert_eq!(now.offset().whole_hours(), -8);
+    }
+
+    #[test]
+    fn state_through_the_day() {
+        let mut data = fixture("basic");
+        let state_at = |data: &mut data::AppData, at| {
+            pin_now(data, at);
+            current_or_next(data, time::Duration::ZERO, GapBehavior::Hold)
+                .unwrap()
+                .state
+        };
+        let day = datetime!(2026-10-14 00:00);
+        assert_eq!(
+            state_at(&mut data, day.replace_time(time!(07:30))),
+            DayState::BeforeSchool
+        );
+        assert_eq!(
+            state_at(&mut data, day.replace_time(time!(09:00))),
+            DayState::InClass
+        );
+        // Lunch is listed under "breaks".
+        assert_eq!(
+            state_at(&mut data, day.replace_time(time!(10:00))),
+            DayState::Passing
+        );
+        assert_eq!(
+            state_at(&mut data, day.replace_time(time!(12:00))),
+            DayState::AfterSchool
+        );
+    }
+
+    #[test]
+    fn adaptive_precision_in_a_pattern() {
+        let args = |list: &[&str]| parse_args(list.iter().map(|arg| arg.to_string()));
+        assert!(args(&["--adaptive-precision", "90", "--format", "[MM]:[SS]"]).is_err());
+        assert!(args(&["--adaptive-precision", "90", "--format", "tmux"]).is_err());
+        let opts = args(&[
+            "--adaptive-precision",
+            "90",
+            "--format",
+            "[Period] [Adaptive]",
+        ])
+        .unwrap();
+        let mut data = fixture("basic");
+        // Period 2 ends at 09:50.
+        pin_now(&mut data, datetime!(2026-10-14 09:40:00.5));
+        let snap = current_or_next(&data, time::Duration::ZERO, GapBehavior::Hold).unwrap();
+        assert_eq!(
+            render_line(&opts, &data, &opts.format, &snap, "\n"),
+            "Period 2 9m"
+        );
+        pin_now(&mut data, datetime!(2026-10-14 09:48:50.5));
+        let snap = current_or_next(&data, time::Duration::ZERO, GapBehavior::Hold).unwrap();
+        assert_eq!(
+            render_line(&opts, &data, &opts.format, &snap, "\n"),
+            "Period 2 69s"
+        );
+    }
+
+    #[test]
+    fn next_change_in_follows_the_rounding() {
+        let remaining = time::Duration::seconds(150);
+        let render = |round| duration_tokens(remaining, "[MM]", round).fill("[MM]");
+        let later = |round, secs| {
+            duration_tokens(remaining - time::Duration::seconds(secs), "[MM]", round).fill("[MM]")
+        };
+        for round in [Rounding::Down, Rounding::Up, Rounding::Nearest] {
+            let change = next_change_in("[MM]", remaining, round);
+            assert_eq!(later(round, change - 1), render(round));
+            assert_ne!(later(round, change), render(round));
+        }
+        assert_eq!(next_change_in("[MM]", remaining, Rounding::Down), 31);
+        assert_eq!(next_change_in("[MM]", remaining, Rounding::Up), 30);
+        assert_eq!(next_change_in("[MM]", remaining, Rounding::Nearest), 1);
+    }
 }
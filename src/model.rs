@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use time::{Date, Time, Weekday};
+
+// Calendar files from the most specific layer to the least specific one. Only
+// calendar.bell is required; the others may hold just a "* Special Days" section.
+pub const CALENDAR_LAYERS: &[&str] = &["class.bell", "calendar.bell", "district.bell"];
+
+// data.postcard starts with this magic and a format version. Bump the version whenever
+// a change to the serialized structs makes older blobs decode differently.
+pub const DATA_MAGIC: &[u8; 4] = b"BELL";
+pub const DATA_VERSION: u8 = 3;
+
+pub fn data_header() -> Vec<u8> {
+    let mut header = DATA_MAGIC.to_vec();
+    header.push(DATA_VERSION);
+    header
+}
+
+// The postcard payload after the header, or an error naming the mismatch.
+pub fn data_payload(bytes: &[u8]) -> Result<&[u8], String> {
+    let Some(rest) = bytes.strip_prefix(DATA_MAGIC.as_slice()) else {
+        return Err("incompatible data format: missing BELL header".to_string());
+    };
+    match rest.split_first() {
+        Some((&DATA_VERSION, payload)) => Ok(payload),
+        Some((version, _)) => Err(format!(
+            "incompatible data format: version {version}, expected {DATA_VERSION}"
+        )),
+        None => Err("incompatible data format: missing version".to_string()),
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Meta {
+    pub name: String,
+    pub periods: Vec<String>,
+    #[serde(default)]
+    pub icons: HashMap<String, String>,
+    #[serde(default)]
+    pub default_icon: Option<String>,
+    #[serde(default)]
+    pub week_start: WeekStart,
+    #[serde(default)]
+    pub year_start: Option<Date>,
+    #[serde(default)]
+    pub year_end: Option<Date>,
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub day_start: Option<String>,
+    #[serde(default)]
+    pub day_end: Option<String>,
+    // Periods that are breaks rather than classes, e.g. "Passing" or "Lunch".
+    #[serde(default)]
+    pub breaks: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WeekStart {
+    #[default]
+    Monday,
+    Sunday,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Calendar {
+    pub default: Week,
+    pub special: Vec<SpecialDay>,
+}
+
+impl Week {
+    pub fn schedule_for(&self, weekday: Weekday) -> Option<&str> {
+        match weekday {
+            Weekday::Monday => self.mon.as_deref(),
+            Weekday::Tuesday => self.tue.as_deref(),
+            Weekday::Wednesday => self.wed.as_deref(),
+            Weekday::Thursday => self.thu.as_deref(),
+            Weekday::Friday => self.fri.as_deref(),
+            Weekday::Saturday => self.sat.as_deref(),
+            Weekday::Sunday => self.sun.as_deref(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Week {
+    pub mon: Option<String>,
+    pub tue: Option<String>,
+    pub wed: Option<String>,
+    pub thu: Option<String>,
+    pub fri: Option<String>,
+    pub sat: Option<String>,
+    pub sun: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SpecialDay {
+    pub on: Date,
+    pub until: Option<Date>,
+    pub schedule: Option<String>,
+    pub comment: Option<String>,
+    pub overrides: Vec<PeriodOverride>,
+    pub inserts: Vec<PeriodInsert>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PeriodInsert {
+    pub msg: String,
+    pub start: Time,
+    pub seconds: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PeriodOverride {
+    pub msg: String,
+    pub start: Time,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ScheduleStore {
+    pub schedules: HashMap<String, Schedule>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Schedule {
+    pub comment: Option<String>,
+    pub based_on: Option<String>,
+    pub zone: Option<String>,
+    // End of the final entry, which otherwise only marks the last bell.
+    pub last_end: Option<Time>,
+    pub periods: Vec<Period>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Period {
+    pub msg: String,
+    pub start: Time,
+    pub comment: Option<String>,
+    pub ring: bool,
+    // A marker that only sets a boundary; the previous period's name stays on display.
+    pub hidden: bool,
+    // "Announcements | Picture day" rotates through all messages; empty for just `msg`.
+    pub rotation: Vec<String>,
+}
@@ -0,0 +1,67 @@
+use std::{thread, time::Duration};
+
+use rumqttc::{Client, MqttOptions, QoS};
+
+pub struct MqttPublisher {
+    client: Client,
+    topic: String,
+    last: Option<(String, String)>,
+}
+
+impl MqttPublisher {
+    pub fn connect(broker_url: &str, topic: &str) -> Result<Self, String> {
+        let (host, port) = parse_broker_url(broker_url)?;
+        let mut options = MqttOptions::new("bell", host, port);
+        options.set_keep_alive(Duration::from_secs(30));
+        let (client, mut connection) = Client::new(options, 10);
+        thread::spawn(move || {
+            for event in connection.iter() {
+                if let Err(err) = event {
+                    log::warn!("MQTT connection error: {err}");
+                    thread::sleep(Duration::from_secs(5));
+                }
+            }
+        });
+        Ok(Self {
+            client,
+            topic: topic.to_string(),
+            last: None,
+        })
+    }
+
+    pub fn publish_if_changed(&mut self, label: &str, msg: &str, remaining: time::Duration) {
+        let unchanged = self
+            .last
+            .as_ref()
+            .is_some_and(|(last_label, last_msg)| last_label == label && last_msg == msg);
+        if unchanged {
+            return;
+        }
+        self.last = Some((label.to_string(), msg.to_string()));
+        let payload = crate::json_line(label, msg, remaining);
+        if let Err(err) = self
+            .client
+            .publish(self.topic.as_str(), QoS::AtLeastOnce, true, payload)
+        {
+            log::warn!("Failed to publish to MQTT: {err}");
+        }
+    }
+}
+
+fn parse_broker_url(url: &str) -> Result<(String, u16), String> {
+    let address = url
+        .strip_prefix("mqtt://")
+        .or_else(|| url.strip_prefix("tcp://"))
+        .unwrap_or(url)
+        .trim_end_matches('/');
+    match address.rsplit_once(':') {
+        Some((host, port)) => {
+            let port = port
+                .parse()
+                .map_err(|_| format!("Invalid port in MQTT broker URL: {url}"))?;
+            Ok((host.to_string(), port))
+        }
+        None if !address.is_empty() => Ok((address.to_string(), 1883)),
+        None => Err(format!("Invalid MQTT broker URL: {url}")),
+    }
+}
@@ -0,0 +1,87 @@
+use notify_rust::{Notification, Timeout, Urgency};
+use time::Time;
+
+#[derive(Debug, Clone)]
+pub struct NotifyOptions {
+    pub urgency: Urgency,
+    pub timeout: Timeout,
+    pub day_over_text: String,
+    pub quiet_hours: Option<(Time, Time)>,
+}
+
+impl Default for NotifyOptions {
+    fn default() -> Self {
+        Self {
+            urgency: Urgency::Normal,
+            timeout: Timeout::Default,
+            day_over_text: "School day over".to_string(),
+            quiet_hours: None,
+        }
+    }
+}
+
+pub fn parse_urgency(value: &str) -> Result<Urgency, String> {
+    match value {
+        "low" => Ok(Urgency::Low),
+        "normal" => Ok(Urgency::Normal),
+        "critical" => Ok(Urgency::Critical),
+        _ => Err(format!(
+            "Invalid value for --notify-urgency: {value} (expected low, normal or critical)"
+        )),
+    }
+}
+
+pub fn parse_timeout(value: &str) -> Result<Timeout, String> {
+    let invalid = || format!("Invalid value for --notify-timeout: {value}");
+    match value {
+        "default" => return Ok(Timeout::Default),
+        "never" => return Ok(Timeout::Never),
+        _ => {}
+    }
+    let millis = if let Some(ms) = value.strip_suffix("ms") {
+        ms.parse::<u32>().map_err(|_| invalid())?
+    } else {
+        let secs = value.strip_suffix('s').unwrap_or(value);
+        secs.parse::<u32>()
+            .ok()
+            .and_then(|secs| secs.checked_mul(1000))
+            .ok_or_else(invalid)?
+    };
+    Ok(Timeout::Milliseconds(millis))
+}
+
+// "22:00-06:00"; a window whose end is earlier than its start wraps past midnight.
+pub fn parse_quiet_hours(value: &str) -> Result<(Time, Time), String> {
+    let invalid = || format!("Invalid value for --quiet-hours: {value}");
+    let (start, end) = value.split_once('-').ok_or_else(invalid)?;
+    let start = crate::parse::parse_start_time(start.trim()).map_err(|_| invalid())?;
+    let end = crate::parse::parse_start_time(end.trim()).map_err(|_| invalid())?;
+    if start == end {
+        return Err(invalid());
+    }
+    Ok((start, end))
+}
+
+impl NotifyOptions {
+    pub fn is_quiet(&self, now: Time) -> bool {
+        match self.quiet_hours {
+            Some((start, end)) if start < end => now >= start && now < end,
+            Some((start, end)) => now >= start || now < end,
+            None => false,
+        }
+    }
+}
+
+pub fn send(opts: &NotifyOptions, summary: &str, body: &str) {
+    let mut notification = Notification::new();
+    notification
+        .appname("bell")
+        .summary(summary)
+        .body(body)
+        .timeout(opts.timeout);
+    #[cfg(not(target_os = "macos"))]
+    notification.urgency(opts.urgency);
+    if let Err(err) = notification.show() {
+        log::warn!("Failed to show notification: {err}");
+    }
+}
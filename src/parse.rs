@@ -0,0 +1,884 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use time::{Date, Time, macros::format_description};
+
+use crate::model::{
+    Calendar, Meta, Period, PeriodInsert, PeriodOverride, Schedule, ScheduleStore, SpecialDay, Week,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    InvalidMeta(String),
+    EmptyMetaName,
+    EmptyMetaPeriods,
+    InvalidMetaPeriod(usize),
+    DuplicateMetaPeriod(String),
+    InvalidMetaTime(&'static str, String),
+    InvalidCalendarStart,
+    MissingDefaultSchedule,
+    InvalidDefaultDay(String),
+    InvalidSpecialDay(String),
+    InvalidDate(String),
+    OverrideBeforeSpecialDay,
+    OverrideOnNote(String),
+    InvalidOverride(String),
+    InvalidInsert(String),
+    InsertPastMidnight(String),
+    DuplicateSchedule(String),
+    MissingScheduleName,
+    PeriodBeforeHeader,
+    InvalidPeriod(String),
+    InvalidTime(String),
+    InvalidDuration(String),
+    MissingDayStart(String),
+    MixedPeriodTimes(String),
+    InvalidBlock(String),
+    DuplicateBlock(String),
+    NestedBlock(String),
+    UnterminatedBlock(String),
+    UndefinedBlock(String),
+    CyclicBlock(Vec<String>),
+    InvalidScheduleHeader(String),
+    UndefinedSchedule(String),
+    CyclicSchedule(Vec<String>),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::InvalidMeta(err) => write!(f, "Invalid meta.json: {err}"),
+            ParseError::EmptyMetaName => write!(f, "meta.json: \"name\" must not be empty"),
+            ParseError::EmptyMetaPeriods => {
+                write!(f, "meta.json: \"periods\" must list at least one period")
+            }
+            ParseError::InvalidMetaPeriod(idx) => {
+                write!(f, "meta.json: \"periods\"[{idx}] must not be empty")
+            }
+            ParseError::DuplicateMetaPeriod(name) => {
+                write!(
+                    f,
+                    "meta.json: period '{name}' is listed more than once in \"periods\""
+                )
+            }
+            ParseError::InvalidMetaTime(key, raw) => {
+                write!(
+                    f,
+                    "meta.json: \"{key}\" must be a time like 08:00, not '{raw}'"
+                )
+            }
+            ParseError::InvalidCalendarStart => write!(f, "Invalid start of calendar file"),
+            ParseError::MissingDefaultSchedule => write!(f, "Missing schedule for default week"),
+            ParseError::InvalidDefaultDay(day) => {
+                write!(f, "Invalid day '{day}' in default week")
+            }
+            ParseError::InvalidSpecialDay(line) => write!(f, "Invalid special day entry '{line}'"),
+            ParseError::InvalidDate(date) => write!(f, "Invalid date '{date}' in calendar"),
+            ParseError::OverrideBeforeSpecialDay => {
+                write!(f, "Period override found before any special day")
+            }
+            ParseError::OverrideOnNote(line) => {
+                write!(f, "'{line}' follows a special day without a schedule")
+            }
+            ParseError::InvalidOverride(line) => write!(f, "Invalid period override '{line}'"),
+            ParseError::InvalidInsert(line) => write!(f, "Invalid period insert '{line}'"),
+            ParseError::InsertPastMidnight(msg) => {
+                write!(f, "Insert '{msg}' pushes the school day past midnight")
+            }
+            ParseError::DuplicateSchedule(name) => {
+                write!(f, "Duplicate schedule name '{name}' in schedules.bell")
+            }
+            ParseError::MissingScheduleName => write!(f, "Missing schedule name in schedules.bell"),
+            ParseError::PeriodBeforeHeader => {
+                write!(f, "Schedule period found before any schedule header")
+            }
+            ParseError::InvalidPeriod(line) => {
+                write!(f, "Invalid period entry '{line}' in schedules.bell")
+            }
+            ParseError::InvalidTime(raw) => write!(f, "Invalid time '{raw}'"),
+            ParseError::InvalidDuration(raw) => write!(f, "Invalid duration '{raw}'"),
+            ParseError::MissingDayStart(name) => {
+                write!(f, "Schedule '{name}' uses durations without an @start time")
+            }
+            ParseError::MixedPeriodTimes(name) => {
+                write!(f, "Schedule '{name}' mixes start times and durations")
+            }
+            ParseError::InvalidBlock(line) => {
+                write!(f, "Invalid block definition '{line}' in schedules.bell")
+            }
+            ParseError::DuplicateBlock(name) => {
+                write!(f, "Duplicate block '{name}' in schedules.bell")
+            }
+            ParseError::NestedBlock(name) => {
+                write!(f, "Nested @define inside block '{name}' in schedules.bell")
+            }
+            ParseError::UnterminatedBlock(name) => {
+                write!(f, "Unterminated block '{name}' in schedules.bell")
+            }
+            ParseError::UndefinedBlock(name) => {
+                write!(f, "Undefined block '{name}' referenced in schedules.bell")
+            }
+            ParseError::InvalidScheduleHeader(header) => {
+                write!(f, "Invalid schedule header '{header}' in schedules.bell")
+            }
+            ParseError::UndefinedSchedule(name) => {
+                write!(
+                    f,
+                    "Undefined schedule '{name}' used as based_on in schedules.bell"
+                )
+            }
+            ParseError::CyclicSchedule(chain) => {
+                write!(
+                    f,
+                    "Cyclic based_on chain in schedules.bell: {}",
+                    chain.join(" -> ")
+                )
+            }
+            ParseError::CyclicBlock(chain) => {
+                write!(
+                    f,
+                    "Cyclic block reference in schedules.bell: {}",
+                    chain.join(" -> ")
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// Files saved by Windows editors may start with a BOM, which `trim` leaves alone.
+// CRLF endings need nothing extra: `lines` drops the `\r` along with the `\n`.
+fn strip_bom(src: &str) -> &str {
+    src.strip_prefix('\u{feff}').unwrap_or(src)
+}
+
+pub fn parse_meta(src: &str) -> Result<Meta, ParseError> {
+    let meta: Meta = serde_json::from_str(strip_bom(src))
+        .map_err(|err| ParseError::InvalidMeta(err.to_string()))?;
+    if meta.name.trim().is_empty() {
+        return Err(ParseError::EmptyMetaName);
+    }
+    if meta.periods.is_empty() {
+        return Err(ParseError::EmptyMetaPeriods);
+    }
+    let mut seen = HashSet::new();
+    for (idx, period) in meta.periods.iter().enumerate() {
+        if period.trim().is_empty() {
+            return Err(ParseError::InvalidMetaPeriod(idx));
+        }
+        if !seen.insert(period.as_str()) {
+            return Err(ParseError::DuplicateMetaPeriod(period.clone()));
+        }
+    }
+    for (key, raw) in [("day_start", &meta.day_start), ("day_end", &meta.day_end)] {
+        if let Some(raw) = raw
+            && parse_start_time(raw).is_err()
+        {
+            return Err(ParseError::InvalidMetaTime(key, raw.clone()));
+        }
+    }
+    Ok(meta)
+}
+
+fn parse_date(raw: &str) -> Result<Date, ParseError> {
+    Date::parse(raw, format_description!("[month]/[day]/[year]"))
+        .or_else(|_| Date::parse(raw, format_description!("[year]-[month]-[day]")))
+        .map_err(|_| ParseError::InvalidDate(raw.to_string()))
+}
+
+// Ranges are "a..b", or "a-b" as long as the dash splits two valid dates, so ISO dates
+// like 2025-12-20-2026-01-05 still work.
+fn parse_date_range(raw: &str) -> Result<(Date, Option<Date>), ParseError> {
+    if let Some((on, until)) = raw.split_once("..") {
+        return Ok((parse_date(on)?, Some(parse_date(until)?)));
+    }
+    if let Ok(date) = parse_date(raw) {
+        return Ok((date, None));
+    }
+    raw.match_indices('-')
+        .find_map(|(idx, _)| {
+            let on = parse_date(&raw[..idx]).ok()?;
+            let until = parse_date(&raw[idx + 1..]).ok()?;
+            Some((on, Some(until)))
+        })
+        .ok_or_else(|| ParseError::InvalidDate(raw.to_string()))
+}
+
+pub fn parse_calendar(src: &str) -> Result<Calendar, ParseError> {
+    let mut iter = strip_bom(src).lines().map(|el| el.trim()).peekable();
+    // Layer calendars may skip the default week; the loop below then stops right away.
+    if iter.peek() != Some(&"* Special Days") && iter.next() != Some("* Default Week") {
+        return Err(ParseError::InvalidCalendarStart);
+    }
+    let mut default_week = Week::default();
+    for next in iter.by_ref() {
+        let next = match next.split_once('#') {
+            Some((left, _)) => left.trim(),
+            None => next,
+        };
+        if next.is_empty() {
+            continue;
+        }
+        if next == "* Special Days" {
+            break;
+        }
+        let mut parts = next.split_whitespace();
+        let day = parts.next().unwrap_or("").trim();
+        let schedule = parts.next().unwrap_or("").trim();
+        if schedule.is_empty() {
+            return Err(ParseError::MissingDefaultSchedule);
+        }
+        match day {
+            "Sun" => default_week.sun = Some(schedule.to_string()),
+            "Mon" => default_week.mon = Some(schedule.to_string()),
+            "Tue" => default_week.tue = Some(schedule.to_string()),
+            "Wed" => default_week.wed = Some(schedule.to_string()),
+            "Thu" => default_week.thu = Some(schedule.to_string()),
+            "Fri" => default_week.fri = Some(schedule.to_string()),
+            "Sat" => default_week.sat = Some(schedule.to_string()),
+            _ => return Err(ParseError::InvalidDefaultDay(day.to_string())),
+        }
+    }
+    let mut special_days: Vec<SpecialDay> = Vec::new();
+    for next in iter {
+        if next.is_empty() {
+            continue;
+        }
+        // "insert 09:00 Assembly 30m" adds a period and pushes every later one back.
+        if let Some(rest) = next.strip_prefix("insert ") {
+            let Some(special) = special_days.last_mut() else {
+                return Err(ParseError::OverrideBeforeSpecialDay);
+            };
+            if special.schedule.is_none() {
+                return Err(ParseError::OverrideOnNote(next.to_string()));
+            }
+            let invalid = || ParseError::InvalidInsert(next.to_string());
+            let (start, rest) = rest
+                .trim()
+                .split_once(char::is_whitespace)
+                .ok_or_else(invalid)?;
+            let (msg, length) = rest
+                .trim()
+                .rsplit_once(char::is_whitespace)
+                .ok_or_else(invalid)?;
+            special.inserts.push(PeriodInsert {
+                msg: msg.trim().to_string(),
+                start: parse_start_time(start)?,
+                seconds: parse_seconds(length)?,
+            });
+            continue;
+        }
+        if let Some(rest) = next.strip_prefix("override ") {
+            let Some(special) = special_days.last_mut() else {
+                return Err(ParseError::OverrideBeforeSpecialDay);
+            };
+            if special.schedule.is_none() {
+                return Err(ParseError::OverrideOnNote(next.to_string()));
+            }
+            let rest = rest.trim();
+            let (msg, start) = rest
+                .rsplit_once(char::is_whitespace)
+                .ok_or_else(|| ParseError::InvalidOverride(next.to_string()))?;
+            special.overrides.push(PeriodOverride {
+                msg: msg.trim().to_string(),
+                start: parse_start_time(start)?,
+            });
+            continue;
+        }
+        let (before_comment, comment) = match next.split_once('#') {
+            Some((left, right)) => (left.trim(), Some(right.trim().to_string())),
+            None => (next.trim(), None),
+        };
+        let mut parts = before_comment.split_whitespace();
+        let date_str = parts.next().unwrap_or("").trim();
+        let schedule = parts.next().unwrap_or("").trim();
+        // A date with only a comment annotates the calendar without changing the schedule.
+        if date_str.is_empty() || (schedule.is_empty() && comment.is_none()) {
+            return Err(ParseError::InvalidSpecialDay(next.to_string()));
+        }
+        let (on, until) = parse_date_range(date_str)?;
+        special_days.push(SpecialDay {
+            on,
+            until,
+            schedule: (!schedule.is_empty()).then(|| schedule.to_string()),
+            comment,
+            overrides: Vec::new(),
+            inserts: Vec::new(),
+        });
+    }
+    Ok(Calendar {
+        default: default_week,
+        special: special_days,
+    })
+}
+
+pub fn parse_schedules(src: &str) -> Result<ScheduleStore, ParseError> {
+    let iter = expand_blocks(strip_bom(src).lines().map(|el| el.trim()))?.into_iter();
+    let mut schedules: HashMap<String, Schedule> = HashMap::new();
+    let mut current_name: Option<String> = None;
+    let mut current_comment: Option<String> = None;
+    let mut current_parent: Option<String> = None;
+    let mut current_zone: Option<String> = None;
+    let mut current_last_end: Option<Time> = None;
+    let mut current_periods: Vec<Period> = Vec::new();
+    let mut cursor: Option<Time> = None;
+    let mut relative: Option<bool> = None;
+
+    for next in iter {
+        if next.is_empty() {
+            continue;
+        }
+        if let Some(header) = next.strip_prefix('*') {
+            if let Some(name) = current_name.take() {
+                let schedule = Schedule {
+                    comment: current_comment.take(),
+                    based_on: current_parent.take(),
+                    zone: current_zone.take(),
+                    // A schedule written in durations ends where its last period does.
+                    last_end: current_last_end
+                        .take()
+                        .or(cursor.filter(|_| relative == Some(true))),
+                    periods: std::mem::take(&mut current_periods),
+                };
+                if schedules.contains_key(&name) {
+                    return Err(ParseError::DuplicateSchedule(name));
+                }
+                schedules.insert(name, schedule);
+            }
+            let header = header.trim();
+            let (before_comment, comment) = match header.split_once('#') {
+                Some((left, right)) => (left.trim(), Some(right.trim().to_string())),
+                None => (header, None),
+            };
+            let mut parts = before_comment.split_whitespace();
+            let name = parts.next().unwrap_or("").trim();
+            if name.is_empty() {
+                return Err(ParseError::MissingScheduleName);
+            }
+            // After the name come optional "based_on <parent>", "zone <zone>" and
+            // "last_end <time>" pairs.
+            while let Some(key) = parts.next() {
+                let invalid = || ParseError::InvalidScheduleHeader(before_comment.to_string());
+                let value = parts.next().ok_or_else(invalid)?.to_string();
+                match key {
+                    "based_on" => current_parent = Some(value),
+                    "zone" => current_zone = Some(value),
+                    "last_end" => current_last_end = Some(parse_start_time(&value)?),
+                    _ => return Err(invalid()),
+                }
+            }
+            current_name = Some(name.to_string());
+            current_comment = comment.filter(|val| !val.is_empty());
+            cursor = None;
+            relative = None;
+            continue;
+        }
+        let Some(name) = current_name.as_deref() else {
+            return Err(ParseError::PeriodBeforeHeader);
+        };
+        if let Some(anchor) = next.strip_prefix("@start") {
+            cursor = Some(parse_start_time(anchor.trim())?);
+            continue;
+        }
+        let (line, comment) = split_period_comment(next);
+        // "silent 12:00 Lunch" is a boundary that --ring-command leaves alone, and
+        // "hidden 12:30 Bell test" one that is never shown; both may be combined.
+        let (mut line, mut ring, mut hidden) = (line, true, false);
+        loop {
+            if let Some(rest) = line.strip_prefix("silent ") {
+                (line, ring) = (rest.trim_start(), false);
+            } else if let Some(rest) = line.strip_prefix("hidden ") {
+                (line, hidden) = (rest.trim_start(), true);
+            } else {
+                break;
+            }
+        }
+        let (start, msg, quoted) = split_start_message(line)?;
+        // A quoted message is taken verbatim, so it never rotates.
+        let rotation: Vec<String> = if quoted {
+            Vec::new()
+        } else {
+            msg.split(" | ")
+                .map(|part| part.trim().to_string())
+                .collect()
+        };
+        let rotation = if rotation.len() > 1 {
+            rotation
+        } else {
+            Vec::new()
+        };
+        let msg = rotation.first().map_or(msg, String::as_str);
+        let is_relative = start.starts_with('+');
+        if *relative.get_or_insert(is_relative) != is_relative {
+            return Err(ParseError::MixedPeriodTimes(name.to_string()));
+        }
+        // A "+50m" line starts where the previous one ended and lasts that long.
+        let start = match start.strip_prefix('+') {
+            Some(duration) => {
+                let begin = cursor.ok_or_else(|| ParseError::MissingDayStart(name.to_string()))?;
+                cursor = Some(advance_time(begin, duration)?);
+                begin
+            }
+            None => parse_start_time(start)?,
+        };
+        current_periods.push(Period {
+            start,
+            msg: msg.to_string(),
+            comment,
+            ring,
+            hidden,
+            rotation,
+        });
+    }
+    if let Some(name) = current_name.take() {
+        let schedule = Schedule {
+            comment: current_comment.take(),
+            based_on: current_parent.take(),
+            zone: current_zone.take(),
+            last_end: current_last_end
+                .take()
+                .or(cursor.filter(|_| relative == Some(true))),
+            periods: std::mem::take(&mut current_periods),
+        };
+        if schedules.contains_key(&name) {
+            return Err(ParseError::DuplicateSchedule(name));
+        }
+        schedules.insert(name, schedule);
+    }
+
+    let mut resolved = HashMap::new();
+    let mut names: Vec<&String> = schedules.keys().collect();
+    names.sort();
+    for name in names {
+        resolve_schedule(name, &schedules, &mut Vec::new(), &mut resolved)?;
+    }
+    for (name, periods) in resolved {
+        if let Some(schedule) = schedules.get_mut(&name) {
+            schedule.periods = periods;
+        }
+    }
+    Ok(ScheduleStore { schedules })
+}
+
+// A child schedule starts from its parent's periods; a child period with the same
+// message replaces the parent's, any other one is added.
+fn resolve_schedule<'a>(
+    name: &'a str,
+    schedules: &'a HashMap<String, Schedule>,
+    stack: &mut Vec<&'a str>,
+    resolved: &mut HashMap<String, Vec<Period>>,
+) -> Result<Vec<Period>, ParseError> {
+    if let Some(periods) = resolved.get(name) {
+        return Ok(periods.clone());
+    }
+    let schedule = schedules
+        .get(name)
+        .ok_or_else(|| ParseError::UndefinedSchedule(name.to_string()))?;
+    let Some(parent) = schedule.based_on.as_deref() else {
+        return Ok(schedule.periods.clone());
+    };
+    if stack.contains(&name) {
+        let mut chain: Vec<String> = stack.iter().map(|name| name.to_string()).collect();
+        chain.push(name.to_string());
+        return Err(ParseError::CyclicSchedule(chain));
+    }
+    stack.push(name);
+    let mut periods = resolve_schedule(parent, schedules, stack, resolved)?;
+    stack.pop();
+    for period in &schedule.periods {
+        match periods
+            .iter_mut()
+            .find(|existing| existing.msg == period.msg)
+        {
+            Some(existing) => *existing = period.clone(),
+            None => periods.push(period.clone()),
+        }
+    }
+    periods.sort_by_key(|period| period.start);
+    resolved.insert(name.to_string(), periods.clone());
+    Ok(periods)
+}
+
+fn expand_blocks<'a, I>(lines: I) -> Result<Vec<&'a str>, ParseError>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let mut blocks: HashMap<&str, Vec<&str>> = HashMap::new();
+    let mut body = Vec::new();
+    let mut current_block: Option<(&str, Vec<&str>)> = None;
+    for line in lines {
+        if let Some((name, block_lines)) = current_block.as_mut() {
+            if line == "}" {
+                let name = *name;
+                let block_lines = std::mem::take(block_lines);
+                current_block = None;
+                if blocks.insert(name, block_lines).is_some() {
+                    return Err(ParseError::DuplicateBlock(name.to_string()));
+                }
+            } else if line.starts_with("@define") {
+                return Err(ParseError::NestedBlock(name.to_string()));
+            } else {
+                block_lines.push(line);
+            }
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("@define") {
+            let name = rest
+                .trim()
+                .strip_suffix('{')
+                .map(|name| name.trim())
+                .filter(|name| !name.is_empty() && !name.contains(char::is_whitespace))
+                .ok_or_else(|| ParseError::InvalidBlock(line.to_string()))?;
+            current_block = Some((name, Vec::new()));
+            continue;
+        }
+        body.push(line);
+    }
+    if let Some((name, _)) = current_block {
+        return Err(ParseError::UnterminatedBlock(name.to_string()));
+    }
+    let mut expanded = Vec::with_capacity(body.len());
+    let mut stack = Vec::new();
+    expand_block_lines(&body, &blocks, &mut stack, &mut expanded)?;
+    Ok(expanded)
+}
+
+fn expand_block_lines<'a>(
+    lines: &[&'a str],
+    blocks: &HashMap<&'a str, Vec<&'a str>>,
+    stack: &mut Vec<&'a str>,
+    out: &mut Vec<&'a str>,
+) -> Result<(), ParseError> {
+    for &line in lines {
+        let Some(rest) = line.strip_prefix("@use") else {
+            out.push(line);
+            continue;
+        };
+        let name = rest.trim();
+        let Some(block) = blocks.get(name) else {
+            return Err(ParseError::UndefinedBlock(name.to_string()));
+        };
+        if stack.contains(&name) {
+            let mut chain: Vec<String> = stack.iter().map(|name| name.to_string()).collect();
+            chain.push(name.to_string());
+            return Err(ParseError::CyclicBlock(chain));
+        }
+        stack.push(name);
+        expand_block_lines(block, blocks, stack, out)?;
+        stack.pop();
+    }
+    Ok(())
+}
+
+// Only a `#` preceded by whitespace starts a comment, so messages like "Room#4" stay intact.
+// Inside a quoted message a `#` never starts a comment.
+fn split_period_comment(line: &str) -> (&str, Option<String>) {
+    let Some((idx, _)) = line.match_indices(" #").find(|(idx, _)| {
+        line[..*idx].matches('"').count().is_multiple_of(2)
+            && line[..*idx]
+                .split_once(char::is_whitespace)
+                .is_some_and(|(_, msg)| !msg.trim().is_empty())
+    }) else {
+        return (line, None);
+    };
+    let comment = line[idx + 2..].trim();
+    let comment = (!comment.is_empty()).then(|| comment.to_string());
+    (line[..idx].trim_end(), comment)
+}
+
+// The start, the message and whether the message was quoted.
+fn split_start_message(line: &str) -> Result<(&str, &str, bool), ParseError> {
+    let invalid = || ParseError::InvalidPeriod(line.to_string());
+    let (idx, _) = line
+        .char_indices()
+        .find(|(_, ch)| ch.is_whitespace())
+        .ok_or_else(invalid)?;
+    let (start, rest) = line.split_at(idx);
+    let start = start.trim();
+    let msg = rest.trim();
+    // A quoted message is taken verbatim, spaces included, without its quotes. Only an
+    // opening quote right after the time makes it quoted; `Say "hi"` stays as written.
+    let quoted = msg
+        .strip_prefix('"')
+        .and_then(|quoted| quoted.strip_suffix('"'));
+    let msg = quoted.unwrap_or(msg);
+    if start.is_empty() || msg.is_empty() {
+        return Err(invalid());
+    }
+    Ok((start, msg, quoted.is_some()))
+}
+
+fn advance_time(begin: Time, raw: &str) -> Result<Time, ParseError> {
+    let invalid = || ParseError::InvalidDuration(raw.to_string());
+    let (hour, minute, second) = begin.as_hms();
+    let end =
+        u32::from(hour) * 3600 + u32::from(minute) * 60 + u32::from(second) + parse_seconds(raw)?;
+    if end >= 24 * 3600 {
+        return Err(invalid());
+    }
+    Time::from_hms(
+        (end / 3600) as u8,
+        (end % 3600 / 60) as u8,
+        (end % 60) as u8,
+    )
+    .map_err(|_| invalid())
+}
+
+// Durations like "50m", "1h30m", "1.5h" or "12.5m", which must come out to whole seconds.
+pub fn parse_seconds(raw: &str) -> Result<u32, ParseError> {
+    let invalid = || ParseError::InvalidDuration(raw.to_string());
+    let mut seconds = 0.0;
+    let mut rest = raw;
+    while !rest.is_empty() {
+        let idx = rest.find(['h', 'm']).ok_or_else(invalid)?;
+        let number = &rest[..idx];
+        // Plain decimals only, so "1.2.3m", "1e2m" or "infm" are rejected.
+        if number.is_empty()
+            || number.matches('.').count() > 1
+            || !number.chars().all(|ch| ch.is_ascii_digit() || ch == '.')
+        {
+            return Err(invalid());
+        }
+        let value: f64 = number.parse().map_err(|_| invalid())?;
+        seconds += if rest[idx..].starts_with('h') {
+            value * 3600.0
+        } else {
+            value * 60.0
+        };
+        rest = &rest[idx + 1..];
+    }
+    if raw.is_empty() || (seconds - seconds.round()).abs() > 1e-6 || seconds >= 24.0 * 3600.0 {
+        return Err(invalid());
+    }
+    Ok(seconds.round() as u32)
+}
+
+// A special day's overrides and inserts applied to a schedule's periods and end. An insert
+// pushes back every period from its start on, and the end of the day with them.
+pub fn apply_special_day(
+    periods: &mut Vec<Period>,
+    last_end: &mut Option<Time>,
+    special: &SpecialDay,
+) -> Result<(), ParseError> {
+    for period_override in &special.overrides {
+        for period in periods.iter_mut() {
+            if period.msg == period_override.msg {
+                period.start = period_override.start;
+            }
+        }
+    }
+    for insert in &special.inserts {
+        let past_midnight = || ParseError::InsertPastMidnight(insert.msg.clone());
+        // The insert itself may end on midnight, but nothing may start there or later.
+        let day = 24 * 3600;
+        if seconds_of_day(insert.start) + insert.seconds > day {
+            return Err(past_midnight());
+        }
+        let shift = |time: Time| {
+            let secs = seconds_of_day(time) + insert.seconds;
+            if secs >= day {
+                return Err(past_midnight());
+            }
+            Ok(Time::MIDNIGHT + time::Duration::seconds(i64::from(secs)))
+        };
+        for period in periods
+            .iter_mut()
+            .filter(|period| period.start >= insert.start)
+        {
+            period.start = shift(period.start)?;
+        }
+        // An insert at or after the end of the day becomes its new last period.
+        if let Some(end) = last_end.as_mut() {
+            *end = shift((*end).max(insert.start))?;
+        }
+        periods.push(Period {
+            msg: insert.msg.clone(),
+            start: insert.start,
+            comment: None,
+            ring: true,
+            hidden: false,
+            rotation: Vec::new(),
+        });
+    }
+    periods.sort_by_key(|period| period.start);
+    Ok(())
+}
+
+fn seconds_of_day(time: Time) -> u32 {
+    let (hour, minute, second) = time.as_hms();
+    u32::from(hour) * 3600 + u32::from(minute) * 60 + u32::from(second)
+}
+
+// Every special day's inserts must keep its schedule within the day.
+pub fn check_inserts(
+    meta: &Meta,
+    calendars: &[Calendar],
+    schedules: &ScheduleStore,
+) -> Result<(), ParseError> {
+    let day_end = meta.day_end.as_deref().map(parse_start_time).transpose()?;
+    for special in calendars.iter().flat_map(|calendar| &calendar.special) {
+        let Some(schedule) = special
+            .schedule
+            .as_ref()
+            .and_then(|name| schedules.schedules.get(name))
+        else {
+            continue;
+        };
+        let mut periods = schedule.periods.clone();
+        let mut last_end = schedule.last_end.or(day_end);
+        apply_special_day(&mut periods, &mut last_end, special)?;
+    }
+    Ok(())
+}
+
+pub fn parse_start_time(raw: &str) -> Result<Time, ParseError> {
+    let invalid = || ParseError::InvalidTime(raw.to_string());
+    let (hour_str, minute_str) = raw.split_once(':').ok_or_else(invalid)?;
+    let hour: u8 = hour_str.trim().parse().map_err(|_| invalid())?;
+    let minute: u8 = minute_str.trim().parse().map_err(|_| invalid())?;
+    Time::from_hms(hour, minute, 0).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::{date, time};
+
+    use super::*;
+
+    #[test]
+    fn default_week_line_with_inline_comment() {
+        let calendar = parse_calendar("* Default Week\nMon LAHS # regular\n# Tue off\n").unwrap();
+        assert_eq!(calendar.default.mon.as_deref(), Some("LAHS"));
+        assert_eq!(calendar.default.tue, None);
+    }
+
+    fn periods(src: &str) -> Vec<Period> {
+        let mut store = parse_schedules(src).unwrap();
+        store.schedules.remove("day").unwrap().periods
+    }
+
+    #[test]
+    fn unquoted_message() {
+        let periods = periods("* day\n08:00 Biology 101 # room 4\n09:00 Room#4\n");
+        assert_eq!(periods[0].msg, "Biology 101");
+        assert_eq!(periods[0].comment.as_deref(), Some("room 4"));
+        assert_eq!(periods[1].msg, "Room#4");
+    }
+
+    #[test]
+    fn quoted_message_is_taken_verbatim() {
+        let periods = periods("* day\n08:00 \"  Biology 101 # lab | \" # room 4\n09:00 \"Chem\"\n");
+        assert_eq!(periods[0].msg, "  Biology 101 # lab | ");
+        assert_eq!(periods[0].comment.as_deref(), Some("room 4"));
+        assert!(periods[0].rotation.is_empty());
+        assert_eq!(periods[1].msg, "Chem");
+    }
+
+    #[test]
+    fn unquoted_message_ending_in_a_quote() {
+        let periods = periods("* day\n08:00 Say \"hi\"\n09:00 Announcements | Say \"hi\"\n");
+        assert_eq!(periods[0].msg, "Say \"hi\"");
+        assert_eq!(periods[1].msg, "Announcements");
+        assert_eq!(periods[1].rotation, ["Announcements", "Say \"hi\""]);
+    }
+
+    #[test]
+    fn bom_and_crlf_calendar() {
+        let src = "\u{feff}* Default Week\r\nMon regular\r\n* Special Days\r\n12/24/2026 holiday # Eve\r\n";
+        let calendar = parse_calendar(src).unwrap();
+        assert_eq!(calendar.default.mon.as_deref(), Some("regular"));
+        assert_eq!(calendar.special[0].schedule.as_deref(), Some("holiday"));
+        assert_eq!(calendar.special[0].comment.as_deref(), Some("Eve"));
+    }
+
+    #[test]
+    fn bom_and_crlf_schedules() {
+        let src = "\u{feff}* day # Regular\r\n08:00 Period 1\r\n08:50 Period 2\r\n";
+        let store = parse_schedules(src).unwrap();
+        let schedule = &store.schedules["day"];
+        assert_eq!(schedule.comment.as_deref(), Some("Regular"));
+        assert_eq!(schedule.periods[1].msg, "Period 2");
+    }
+
+    #[test]
+    fn bom_and_crlf_meta() {
+        let src = "\u{feff}{\r\n  \"name\": \"LAHS\",\r\n  \"periods\": [\"Period 1\"]\r\n}\r\n";
+        let meta = parse_meta(src).unwrap();
+        assert_eq!(meta.name, "LAHS");
+        assert_eq!(meta.periods, ["Period 1"]);
+    }
+
+    #[test]
+    fn durations_set_the_last_end() {
+        let src = "* day\n@start 08:00\n+50m Period 1\n+10m Passing\n\
+                   * fixed last_end 10:00\n@start 08:00\n+50m Period 1\n\
+                   * clock\n08:00 Period 1\n";
+        let store = parse_schedules(src).unwrap();
+        assert_eq!(store.schedules["day"].last_end, Some(time!(09:00)));
+        assert_eq!(store.schedules["fixed"].last_end, Some(time!(10:00)));
+        assert_eq!(store.schedules["clock"].last_end, None);
+    }
+
+    #[test]
+    fn meta_day_window_must_be_times() {
+        let meta = |window: &str| {
+            parse_meta(&format!(
+                "{{\"name\": \"LAHS\", \"periods\": [\"Period 1\"], {window}}}"
+            ))
+        };
+        assert!(meta("\"day_start\": \"07:30\", \"day_end\": \"15:10\"").is_ok());
+        assert!(matches!(
+            meta("\"day_start\": \"7.30\""),
+            Err(ParseError::InvalidMetaTime("day_start", raw)) if raw == "7.30"
+        ));
+        assert!(matches!(
+            meta("\"day_end\": \"25:00\""),
+            Err(ParseError::InvalidMetaTime("day_end", _))
+        ));
+    }
+
+    #[test]
+    fn iso_date_ranges() {
+        let range = Ok((date!(2025 - 12 - 20), Some(date!(2026 - 01 - 05))));
+        assert_eq!(parse_date_range("2025-12-20..2026-01-05"), range);
+        assert_eq!(parse_date_range("2025-12-20-2026-01-05"), range);
+        assert_eq!(
+            parse_date_range("2025-12-20"),
+            Ok((date!(2025 - 12 - 20), None))
+        );
+        assert!(matches!(
+            parse_date_range("2025-12-20..2026-13-05"),
+            Err(ParseError::InvalidDate(raw)) if raw == "2026-13-05"
+        ));
+        assert!(matches!(
+            parse_date_range("2025-12-20-2026-13-05"),
+            Err(ParseError::InvalidDate(raw)) if raw == "2025-12-20-2026-13-05"
+        ));
+    }
+
+    #[test]
+    fn inserts_must_not_cross_midnight() {
+        let meta = parse_meta(r#"{"name": "LAHS", "periods": ["A"]}"#).unwrap();
+        let schedules = parse_schedules("* day\n08:00 A\n23:45 B\n").unwrap();
+        let check = |insert: &str| {
+            let calendar = parse_calendar(&format!(
+                "* Default Week\nMon day\n* Special Days\n10/19/2026 day\n{insert}\n"
+            ))
+            .unwrap();
+            check_inserts(&meta, &[calendar], &schedules)
+        };
+        assert!(check("insert 23:50 Late 10m").is_ok());
+        // B would start at 00:15.
+        assert_eq!(
+            check("insert 08:30 Late 30m"),
+            Err(ParseError::InsertPastMidnight("Late".to_string()))
+        );
+        assert_eq!(
+            check("insert 23:50 Late 30m"),
+            Err(ParseError::InsertPastMidnight("Late".to_string()))
+        );
+    }
+}
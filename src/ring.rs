@@ -0,0 +1,22 @@
+use std::{process::Command, thread};
+
+use crate::data::Period;
+
+// Runs the --ring-command for a bell without holding up the display loop. The period
+// that starts at the bell is passed in BELL_PERIOD.
+pub fn ring(command: &str, period: &Period) {
+    #[cfg(unix)]
+    let mut cmd = Command::new("sh");
+    #[cfg(unix)]
+    cmd.arg("-c");
+    #[cfg(not(unix))]
+    let mut cmd = Command::new("cmd");
+    #[cfg(not(unix))]
+    cmd.arg("/C");
+    cmd.arg(command).env("BELL_PERIOD", &period.msg);
+    thread::spawn(move || match cmd.status() {
+        Ok(status) if !status.success() => log::warn!("Ring command exited with {status}"),
+        Ok(_) => {}
+        Err(err) => log::warn!("Failed to run ring command: {err}"),
+    });
+}